@@ -0,0 +1,112 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::clock::Clock;
+use super::curses_util::ansi_mirror::AnsiMirror;
+use super::curses_util::frame_buffer::FrameBuffer;
+
+/// Records frames into an asciinema v2 cast file, writing only the rows which changed since the
+/// previous frame
+pub struct CastRecorder {
+    output: BufWriter<File>,
+    start: Option<Duration>, // Clock time of the first recorded frame
+    mirror: AnsiMirror,
+}
+
+impl CastRecorder {
+    /// Creates the cast file. The header is written once the first frame's size is known.
+    pub fn create(path: &Path) -> io::Result<CastRecorder> {
+        let output = BufWriter::new(File::create(path)?);
+
+        return Ok(CastRecorder { output, start: None, mirror: AnsiMirror::new() });
+    }
+
+    /// Appends an output event bringing the recording up to date with the frame buffer, timed
+    /// using the given clock
+    pub fn record_frame(&mut self, buffer: &FrameBuffer, clock: &impl Clock) -> io::Result<()> {
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|since_epoch| since_epoch.as_secs()).unwrap_or(0);
+                writeln!(self.output, "{{\"version\": 2, \"width\": {}, \"height\": {}, \"timestamp\": {}}}", buffer.cols(), buffer.rows(), timestamp)?;
+
+                *self.start.insert(clock.elapsed())
+            }
+        };
+
+        let frame_output = self.mirror.update(buffer);
+        if frame_output.is_empty() {
+            return Ok(());
+        }
+
+        let elapsed = (clock.elapsed() - start).as_secs_f64();
+        writeln!(self.output, "[{:.6}, \"o\", \"{}\"]", elapsed, escape_json(&frame_output))?;
+
+        return Ok(());
+    }
+
+    /// Flushes any buffered events to the cast file
+    pub fn finish(mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+/// Escapes text so it can be placed inside a JSON string literal
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            control if (control as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", control as u32)),
+            _ => escaped.push(character),
+        }
+    }
+
+    return escaped;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::{escape_json, CastRecorder};
+    use crate::clock::VirtualClock;
+    use crate::curses_util::frame_buffer::FrameBuffer;
+
+    #[test]
+    fn records_changes_relative_to_first_frame() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-cast-test-{}.cast", std::process::id()));
+        let clock = VirtualClock::new();
+        let mut buffer = FrameBuffer::with_dimensions(1, 4);
+        let mut recorder = CastRecorder::create(&path).unwrap();
+
+        clock.advance(Duration::from_secs(5));
+        buffer.put_char(0, 0, '#');
+        recorder.record_frame(&buffer, &clock).unwrap();
+        clock.advance(Duration::from_millis(500));
+        recorder.record_frame(&buffer, &clock).unwrap();
+        clock.advance(Duration::from_millis(250));
+        buffer.put_char(0, 1, '#');
+        recorder.record_frame(&buffer, &clock).unwrap();
+        recorder.finish().unwrap();
+
+        let cast = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = cast.lines().collect();
+        assert!(lines[0].starts_with(r#"{"version": 2, "width": 4, "height": 1, "#));
+        assert_eq!(r#"[0.000000, "o", "\u001b[1;1H#\u001b[K"]"#, lines[1]);
+        assert_eq!(r#"[0.750000, "o", "\u001b[1;1H##\u001b[K"]"#, lines[2]);
+        assert_eq!(3, lines.len());
+    }
+
+    #[test]
+    fn escapes_quotes_and_control_characters() {
+        assert_eq!("\\u001b[1;1H\\\"#\\\\", escape_json("\x1b[1;1H\"#\\"));
+    }
+}
@@ -0,0 +1,61 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+use std::cell::Cell;
+
+/// A source of time for the game loop, so tests can control how time passes
+pub trait Clock {
+    /// The time that has passed since the clock was created
+    fn elapsed(&self) -> Duration;
+    /// Waits for the given length of time to pass
+    fn sleep(&self, duration: Duration);
+}
+
+/// Real wall-clock time
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock which only moves when told to, so that sleeping returns immediately
+#[cfg(test)]
+pub struct VirtualClock {
+    elapsed: Cell<Duration>,
+}
+
+#[cfg(test)]
+impl VirtualClock {
+    pub fn new() -> VirtualClock {
+        VirtualClock { elapsed: Cell::new(Duration::from_secs(0)) }
+    }
+
+    /// Moves the clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.set(self.elapsed.get() + duration);
+    }
+}
+
+#[cfg(test)]
+impl Clock for VirtualClock {
+    fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
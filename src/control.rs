@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// The longest request line a client can send. Clients sending more without a newline are dropped
+/// so they can't make the game buffer without limit.
+const MAX_REQUEST_LENGTH: usize = 4096;
+/// The most requests answered for one client each frame. Any more wait for the next frame.
+const MAX_REQUESTS_PER_POLL: usize = 64;
+/// How long a client may hold up a frame by not reading its replies before being dropped
+const CONTROL_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// A command sent by an external tool, one JSON object per line
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Report where the camera is
+    Camera,
+    /// Move the camera forward and turn it, in world units and radians
+    Move {
+        #[serde(default)]
+        forward: f64,
+        #[serde(default)]
+        turn: f64,
+    },
+    /// Save a screenshot of the current frame
+    Screenshot,
+    /// End the game
+    Quit,
+}
+
+/// The reply to a control request, sent back as one JSON object per line
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum ControlResponse {
+    Camera { x: f64, y: f64, facing: f64 },
+    Screenshot { path: PathBuf },
+    Ok { ok: bool },
+    Error { error: String },
+}
+
+struct ControlClient {
+    stream: UnixStream,
+    pending_input: Vec<u8>,
+}
+
+/// Listens on a Unix socket for JSON commands which drive the game
+pub struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<ControlClient>,
+}
+
+impl ControlServer {
+    /// Creates the control socket at the given path, replacing one left behind by a game that
+    /// didn't shut down cleanly
+    pub fn bind(path: &Path) -> io::Result<ControlServer> {
+        let is_socket = fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket());
+        if is_socket && UnixStream::connect(path).is_err() {
+            fs::remove_file(path)?;
+        }
+
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+
+        return Ok(ControlServer { path: path.to_path_buf(), listener, clients: Vec::new() });
+    }
+
+    /// Accepts waiting clients and answers the complete requests they've sent using the handler.
+    /// Clients which disconnect, misbehave or stop reading their replies are dropped.
+    pub fn poll(&mut self, mut handle_request: impl FnMut(ControlRequest) -> ControlResponse) {
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() && stream.set_write_timeout(Some(CONTROL_WRITE_TIMEOUT)).is_ok() {
+                self.clients.push(ControlClient { stream, pending_input: Vec::new() });
+            }
+        }
+
+        self.clients.retain_mut(|client| client.answer_requests(&mut handle_request).is_ok());
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl ControlClient {
+    fn answer_requests(&mut self, handle_request: &mut impl FnMut(ControlRequest) -> ControlResponse) -> io::Result<()> {
+        let mut read_buffer = [0u8; 1024];
+        let mut requests_answered = 0;
+        loop {
+            // Answer as lines complete so only a partial line is ever held, unless this frame's
+            // share of requests has run out
+            requests_answered += self.answer_complete_lines(handle_request, MAX_REQUESTS_PER_POLL - requests_answered)?;
+            if requests_answered == MAX_REQUESTS_PER_POLL {
+                return Ok(());
+            }
+            if self.pending_input.len() > MAX_REQUEST_LENGTH {
+                return Err(io::Error::new(ErrorKind::InvalidData, "request line too long"));
+            }
+
+            match self.stream.read(&mut read_buffer) {
+                Ok(0) => return Err(io::Error::from(ErrorKind::UnexpectedEof)),
+                Ok(bytes_read) => self.pending_input.extend_from_slice(&read_buffer[..bytes_read]),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Answers up to the given number of complete request lines, returning how many were answered
+    fn answer_complete_lines(&mut self, handle_request: &mut impl FnMut(ControlRequest) -> ControlResponse, max_requests: usize) -> io::Result<usize> {
+        let mut requests_answered = 0;
+        while requests_answered < max_requests {
+            let line_end = match self.pending_input.iter().position(|byte| *byte == b'\n') {
+                Some(line_end) => line_end,
+                None => break,
+            };
+            let line: Vec<u8> = self.pending_input.drain(..=line_end).collect();
+            let response = match serde_json::from_slice::<ControlRequest>(&line) {
+                Ok(request) => handle_request(request),
+                Err(err) => ControlResponse::Error { error: err.to_string() },
+            };
+
+            // Writes block for at most the write timeout, which drops a client that isn't reading
+            let mut response_line = serde_json::to_vec(&response)?;
+            response_line.push(b'\n');
+            self.stream.set_nonblocking(false)?;
+            self.stream.write_all(&response_line)?;
+            self.stream.set_nonblocking(true)?;
+            requests_answered += 1;
+        }
+
+        return Ok(requests_answered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::time::{Duration, Instant};
+
+    use super::{ControlRequest, ControlResponse, ControlServer, MAX_REQUESTS_PER_POLL, MAX_REQUEST_LENGTH};
+
+    #[test]
+    fn parses_requests() {
+        assert_eq!(ControlRequest::Camera, serde_json::from_str(r#"{"command": "camera"}"#).unwrap());
+        assert_eq!(ControlRequest::Move { forward: 1.5, turn: 0.0 }, serde_json::from_str(r#"{"command": "move", "forward": 1.5}"#).unwrap());
+    }
+
+    #[test]
+    fn writes_flat_responses() {
+        let response = ControlResponse::Camera { x: 1.0, y: 2.0, facing: 0.5 };
+
+        assert_eq!(r#"{"x":1.0,"y":2.0,"facing":0.5}"#, serde_json::to_string(&response).unwrap());
+    }
+
+    #[test]
+    fn answers_requests_over_the_socket() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-control-test-{}.sock", std::process::id()));
+        let mut server = ControlServer::bind(&path).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"{\"command\": \"quit\"}\nnot json\n").unwrap();
+
+        let mut requests = Vec::new();
+        server.poll(|request| {
+            requests.push(request);
+            ControlResponse::Ok { ok: true }
+        });
+
+        let mut responses = BufReader::new(client).lines();
+        assert_eq!(vec![ControlRequest::Quit], requests);
+        assert_eq!(r#"{"ok":true}"#, responses.next().unwrap().unwrap());
+        assert!(responses.next().unwrap().unwrap().starts_with(r#"{"error":"#));
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn drops_client_sending_endless_line() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-control-long-test-{}.sock", std::process::id()));
+        let mut server = ControlServer::bind(&path).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(&[b' '; MAX_REQUEST_LENGTH + 1]).unwrap();
+
+        server.poll(|_| ControlResponse::Ok { ok: true });
+        assert!(server.clients.is_empty());
+    }
+
+    #[test]
+    fn answers_limited_requests_per_poll() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-control-limit-test-{}.sock", std::process::id()));
+        let mut server = ControlServer::bind(&path).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all("{\"command\": \"camera\"}\n".repeat(MAX_REQUESTS_PER_POLL + 1).as_bytes()).unwrap();
+
+        let mut requests_answered = 0;
+        server.poll(|_| {
+            requests_answered += 1;
+            ControlResponse::Ok { ok: true }
+        });
+        assert_eq!(MAX_REQUESTS_PER_POLL, requests_answered);
+
+        server.poll(|_| {
+            requests_answered += 1;
+            ControlResponse::Ok { ok: true }
+        });
+        assert_eq!(MAX_REQUESTS_PER_POLL + 1, requests_answered);
+    }
+
+    #[test]
+    fn drops_client_not_reading_replies() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-control-unread-test-{}.sock", std::process::id()));
+        let mut server = ControlServer::bind(&path).unwrap();
+        // The client stays connected but never reads, while another handle keeps sending requests
+        let client = UnixStream::connect(&path).unwrap();
+        let mut sender = client.try_clone().unwrap();
+        std::thread::spawn(move || {
+            let requests = "{\"command\": \"camera\"}\n".repeat(1000);
+            while sender.write_all(requests.as_bytes()).is_ok() {}
+        });
+
+        server.poll(|_| ControlResponse::Ok { ok: true });
+        assert_eq!(1, server.clients.len());
+
+        let started = Instant::now();
+        while !server.clients.is_empty() && started.elapsed() < Duration::from_secs(10) {
+            let poll_started = Instant::now();
+            server.poll(|_| ControlResponse::Ok { ok: true });
+            assert!(poll_started.elapsed() < Duration::from_secs(1));
+        }
+
+        assert!(server.clients.is_empty());
+        drop(client);
+    }
+
+    #[test]
+    fn replaces_stale_socket_but_not_live_one() {
+        let path = std::env::temp_dir().join(format!("cursed-maze-control-stale-test-{}.sock", std::process::id()));
+        // Dropping the listener without removing the file leaves a socket nobody answers on
+        drop(UnixListener::bind(&path).unwrap());
+
+        let server = ControlServer::bind(&path).unwrap();
+        assert!(ControlServer::bind(&path).is_err());
+        drop(server);
+    }
+}
@@ -0,0 +1,50 @@
+use super::frame_buffer::FrameBuffer;
+
+/// Tracks what a remote ANSI terminal is showing so frames can be sent to it as the rows which
+/// changed since the last one
+pub struct AnsiMirror {
+    last_rows: Vec<String>,
+}
+
+impl AnsiMirror {
+    /// Creates a mirror of a blank terminal, so the first update contains the whole frame
+    pub fn new() -> AnsiMirror {
+        AnsiMirror { last_rows: Vec::new() }
+    }
+
+    /// Produces the escape sequences bringing the terminal up to date with the frame buffer.
+    /// Returns an empty string if nothing changed.
+    pub fn update(&mut self, buffer: &FrameBuffer) -> String {
+        let rows: Vec<String> = (0..buffer.rows()).map(|row| buffer.ansi_row(row)).collect();
+        let mut output = String::new();
+
+        for (row_idx, row_text) in rows.iter().enumerate() {
+            if self.last_rows.get(row_idx) != Some(row_text) {
+                // Move to the row, write it, then clear whatever remains of the old row
+                output.push_str(&format!("\x1b[{};1H{}\x1b[K", row_idx + 1, row_text));
+            }
+        }
+        self.last_rows = rows;
+
+        return output;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::frame_buffer::FrameBuffer;
+    use super::AnsiMirror;
+
+    #[test]
+    fn only_sends_changed_rows() {
+        let mut mirror = AnsiMirror::new();
+        let mut buffer = FrameBuffer::with_dimensions(2, 3);
+        buffer.put_char(0, 0, '#');
+
+        assert_eq!("\x1b[1;1H#\x1b[K\x1b[2;1H\x1b[K", mirror.update(&buffer));
+
+        buffer.put_char(1, 1, '.');
+        assert_eq!("\x1b[2;1H .\x1b[K", mirror.update(&buffer));
+        assert_eq!("", mirror.update(&buffer));
+    }
+}
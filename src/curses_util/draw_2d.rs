@@ -1,6 +1,6 @@
 use std::cmp::{max, min};
 
-use ncurses::{chtype, mvaddch};
+use super::frame_buffer::FrameBuffer;
 
 /// Represents a coordinate in screen space
 #[derive(Copy, Clone, Debug)]
@@ -17,7 +17,7 @@ impl Coordinate {
 }
 
 /// Draw a line on the screen with the following character
-pub fn draw_line(from: Coordinate, to: Coordinate, fill_char: char) {
+pub fn draw_line(buffer: &mut FrameBuffer, from: Coordinate, to: Coordinate, fill_char: char) {
     let (from_lowcol, to_highcol) = if from.col < to.col {
         (&from, &to)
     } else {
@@ -33,7 +33,7 @@ pub fn draw_line(from: Coordinate, to: Coordinate, fill_char: char) {
         let highest_row = max(from_lowcol.row, to_highcol.row);
 
         for row in lowest_row..highest_row {
-            mvaddch(row, from_lowcol.col, fill_char as chtype);
+            buffer.put_char(row, from_lowcol.col, fill_char);
         }
         return;
     }
@@ -46,7 +46,7 @@ pub fn draw_line(from: Coordinate, to: Coordinate, fill_char: char) {
     // For each column, draw a pixel
     for idx in 0..=col_change {
         let current_col = from_lowcol.col + idx;
-        mvaddch(current_row, current_col, fill_char as chtype);
+        buffer.put_char(current_row, current_col, fill_char);
         total_row_change += row_change_per_col;
         let absolute_row_change = total_row_change.abs();
 
@@ -56,9 +56,9 @@ pub fn draw_line(from: Coordinate, to: Coordinate, fill_char: char) {
             let row_move = if row_change > 0 { 1 } else { -1 };
 
             while rows_left_to_change != 0 {
-                rows_left_to_change = rows_left_to_change - row_move;
-                mvaddch(current_row, current_col, '#' as chtype);
-                current_row = current_row + row_move;
+                rows_left_to_change -= row_move;
+                buffer.put_char(current_row, current_col, fill_char);
+                current_row += row_move;
             }
 
             total_row_change -= (total_row_change as i32) as f64;
@@ -66,6 +66,49 @@ pub fn draw_line(from: Coordinate, to: Coordinate, fill_char: char) {
     }
 }
 
+/// How far a line's exact row has to reach into a neighbouring row before that row is softened
+const SOFTEN_THRESHOLD: f64 = 0.3;
+/// How far a line's exact row has to reach into a neighbouring row for the heavier softening character
+const SOFTEN_HEAVY_THRESHOLD: f64 = 0.6;
+
+/// Softens the stair steps of a sloped line by placing lighter characters in blank cells beside
+/// it, wherever the line's exact row falls part way into the neighbouring row
+pub fn soften_line(buffer: &mut FrameBuffer, from: Coordinate, to: Coordinate, heavy_char: char, light_char: char) {
+    let (from_lowcol, to_highcol) = if from.col < to.col {
+        (&from, &to)
+    } else {
+        (&to, &from)
+    };
+
+    let col_change = to_highcol.col - from_lowcol.col;
+    if col_change == 0 {
+        return;
+    }
+    let row_change_per_col = (to_highcol.row - from_lowcol.row) as f64 / col_change as f64;
+
+    // Track rows the same way draw_line does so the softening lines up with its steps. The row
+    // change built up short of a full row is how far the line reaches into the next row.
+    let mut total_row_change: f64 = 0.0;
+    let mut current_row = from_lowcol.row;
+
+    for idx in 0..=col_change {
+        if total_row_change.abs() >= SOFTEN_THRESHOLD {
+            let soft_row = current_row + total_row_change.signum() as i32;
+            let soft_col = from_lowcol.col + idx;
+
+            if buffer.is_blank(soft_row, soft_col) {
+                let soft_char = if total_row_change.abs() >= SOFTEN_HEAVY_THRESHOLD { heavy_char } else { light_char };
+                buffer.put_char(soft_row, soft_col, soft_char);
+            }
+        }
+
+        total_row_change += row_change_per_col;
+        current_row += total_row_change as i32;
+        total_row_change -= (total_row_change as i32) as f64;
+    }
+}
+
+#[allow(dead_code)] // Fields are only read through Debug output
 #[derive(Debug)]
 pub struct TriangleFillErr {
     part: Option<i8>,
@@ -77,26 +120,26 @@ pub struct TriangleFillErr {
 }
 
 /// Fill a triangular region on the screen between 3 arbitrary points with the given fill character (fill_char)
-pub fn fill_triangle(corner1: Coordinate, corner2: Coordinate, corner3: Coordinate, fill_char: char) -> Result<(), TriangleFillErr> {
-    let mut sorted_corners = vec!(&corner1, &corner2, &corner3);
-    sorted_corners.sort_by(|corner_a, corner_b| corner_a.col.cmp(&corner_b.col));
+pub fn fill_triangle(buffer: &mut FrameBuffer, corner1: Coordinate, corner2: Coordinate, corner3: Coordinate, fill_char: char) -> Result<(), TriangleFillErr> {
+    let mut sorted_corners = [&corner1, &corner2, &corner3];
+    sorted_corners.sort_by_key(|corner| corner.col);
     let (corner_lowcol, corner_midcol, corner_highcol) = (sorted_corners[0], sorted_corners[1], sorted_corners[2]);
 
     // Draw lines if the coordinates are all in a line
     if corner_lowcol.row == corner_midcol.row && corner_lowcol.row == corner_highcol.row {
-        draw_line(*corner_lowcol, *corner_highcol, fill_char);
+        draw_line(buffer, *corner_lowcol, *corner_highcol, fill_char);
         return Ok(());
     }
     if corner_lowcol.col == corner_midcol.col && corner_lowcol.col == corner_highcol.col {
         let lowest_row = min(min(corner_lowcol.row, corner_midcol.row), min(corner_lowcol.row, corner_highcol.row));
         let highest_row = max(max(corner_lowcol.row, corner_midcol.row), max(corner_lowcol.row, corner_highcol.row));
 
-        draw_line(Coordinate { row: lowest_row, col: corner_lowcol.col }, Coordinate { row: highest_row, col: corner_lowcol.col }, fill_char);
+        draw_line(buffer, Coordinate { row: lowest_row, col: corner_lowcol.col }, Coordinate { row: highest_row, col: corner_lowcol.col }, fill_char);
         return Ok(());
     }
 
-    let mapped_fill_region = |part: Option<i8>, top_start: &Coordinate, top_end: &Coordinate, bottom_start: &Coordinate, bottom_end: &Coordinate| {
-        fill_region_between_lines(*top_start, *top_end, *bottom_start, *bottom_end, fill_char)
+    let mapped_fill_region = |buffer: &mut FrameBuffer, part: Option<i8>, top_start: &Coordinate, top_end: &Coordinate, bottom_start: &Coordinate, bottom_end: &Coordinate| {
+        fill_region_between_lines(buffer, *top_start, *top_end, *bottom_start, *bottom_end, fill_char)
             .map_err(|err| TriangleFillErr {
                 part,
                 top_start: *top_start,
@@ -117,7 +160,7 @@ pub fn fill_triangle(corner1: Coordinate, corner2: Coordinate, corner3: Coordina
         let top_end = corner_highcol;
         let bottom_end = corner_highcol;
 
-        mapped_fill_region(None, top_start, top_end, bottom_start, bottom_end)?;
+        mapped_fill_region(buffer, None, top_start, top_end, bottom_start, bottom_end)?;
         return Ok(());
     }
     if corner_midcol.col == corner_highcol.col {
@@ -129,7 +172,7 @@ pub fn fill_triangle(corner1: Coordinate, corner2: Coordinate, corner3: Coordina
             (corner_highcol, corner_midcol)
         };
 
-        mapped_fill_region(None, top_start, top_end, bottom_start, bottom_end)?;
+        mapped_fill_region(buffer, None, top_start, top_end, bottom_start, bottom_end)?;
         return Ok(());
     }
 
@@ -141,7 +184,7 @@ pub fn fill_triangle(corner1: Coordinate, corner2: Coordinate, corner3: Coordina
 
     // If the middle point is on the line between the low col corner and high col corner, just draw a line
     if second_midpoint.row == corner_midcol.row {
-        draw_line(*corner_lowcol, *corner_highcol, fill_char);
+        draw_line(buffer, *corner_lowcol, *corner_highcol, fill_char);
         return Ok(());
     }
 
@@ -153,8 +196,8 @@ pub fn fill_triangle(corner1: Coordinate, corner2: Coordinate, corner3: Coordina
     };
 
     // Draw the 2 regions
-    mapped_fill_region(Some(1), corner_lowcol, upper_midpoint, corner_lowcol, lower_midpoint)?;
-    mapped_fill_region(Some(2), upper_midpoint, corner_highcol, lower_midpoint, corner_highcol)?;
+    mapped_fill_region(buffer, Some(1), corner_lowcol, upper_midpoint, corner_lowcol, lower_midpoint)?;
+    mapped_fill_region(buffer, Some(2), upper_midpoint, corner_highcol, lower_midpoint, corner_highcol)?;
 
     return Ok(());
 }
@@ -166,7 +209,7 @@ pub enum RegionFillErr {
 }
 
 /// Fill the area between 2 horizontal lines with the given fill character (fill_char)
-fn fill_region_between_lines(top_line_start: Coordinate, top_line_end: Coordinate, bottom_line_start: Coordinate, bottom_line_end: Coordinate, fill_char: char) -> Result<(), RegionFillErr> {
+fn fill_region_between_lines(buffer: &mut FrameBuffer, top_line_start: Coordinate, top_line_end: Coordinate, bottom_line_start: Coordinate, bottom_line_end: Coordinate, fill_char: char) -> Result<(), RegionFillErr> {
     // Find leftmost points
     let (top_leftmost, top_rightmost) = if top_line_start.col > top_line_end.col {
         (&top_line_end, &top_line_start)
@@ -205,7 +248,7 @@ fn fill_region_between_lines(top_line_start: Coordinate, top_line_end: Coordinat
     for idx in 0..=horiz_change {
         let col = top_leftmost.col + idx;
         for row in top_row..=bottom_row {
-            mvaddch(row, col, fill_char as chtype);
+            buffer.put_char(row, col, fill_char);
         }
 
         top_total_row_change += top_vertchange_per_col;
@@ -227,3 +270,41 @@ fn fill_region_between_lines(top_line_start: Coordinate, top_line_end: Coordinat
 
     return Ok(());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{draw_line, soften_line, Coordinate};
+    use crate::curses_util::frame_buffer::FrameBuffer;
+
+    #[test]
+    fn softens_with_heavy_and_light_characters() {
+        let mut buffer = FrameBuffer::with_dimensions(3, 6);
+        let (from, to) = (Coordinate { row: 0, col: 0 }, Coordinate { row: 1, col: 4 });
+        draw_line(&mut buffer, from, to, '#');
+        soften_line(&mut buffer, from, to, ':', '\'');
+
+        // Half way into the next row gets the light character, three quarters the heavy one
+        assert_eq!("####\n  ':#\n\n", buffer.to_text());
+    }
+
+    #[test]
+    fn softens_only_blank_cells() {
+        let mut buffer = FrameBuffer::with_dimensions(3, 6);
+        let (from, to) = (Coordinate { row: 0, col: 0 }, Coordinate { row: 1, col: 4 });
+        draw_line(&mut buffer, from, to, '#');
+        buffer.put_char(1, 3, 'x');
+        soften_line(&mut buffer, from, to, ':', '\'');
+
+        assert_eq!("####\n  'x#\n\n", buffer.to_text());
+    }
+
+    #[test]
+    fn leaves_level_lines_alone() {
+        let mut buffer = FrameBuffer::with_dimensions(3, 6);
+        let (from, to) = (Coordinate { row: 1, col: 0 }, Coordinate { row: 1, col: 4 });
+        draw_line(&mut buffer, from, to, '#');
+        soften_line(&mut buffer, from, to, ':', '\'');
+
+        assert_eq!("\n#####\n\n", buffer.to_text());
+    }
+}
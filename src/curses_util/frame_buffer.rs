@@ -0,0 +1,381 @@
+use std::cmp::{max, min};
+
+use ncurses::{attr_t, attrset, mvaddstr, A_BOLD, A_DIM, A_NORMAL, A_REVERSE};
+
+/// How a character in the frame buffer is emphasised
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CellStyle {
+    Plain,
+    Bold,
+    Reverse,
+    Dim,
+}
+
+impl CellStyle {
+    fn curses_attr(self) -> attr_t {
+        match self {
+            CellStyle::Plain => A_NORMAL(),
+            CellStyle::Bold => A_BOLD(),
+            CellStyle::Reverse => A_REVERSE(),
+            CellStyle::Dim => A_DIM(),
+        }
+    }
+
+    fn ansi_code(self) -> &'static str {
+        match self {
+            CellStyle::Plain => "\x1b[0m",
+            CellStyle::Bold => "\x1b[1m",
+            CellStyle::Reverse => "\x1b[7m",
+            CellStyle::Dim => "\x1b[2m",
+        }
+    }
+}
+
+/// An off-screen copy of the terminal which collects drawn characters so that each row can be
+/// sent to curses with a single call per style instead of one call per character
+pub struct FrameBuffer {
+    rows: i32,
+    cols: i32,
+    cells: Vec<char>,
+    styles: Vec<CellStyle>,
+    current_style: CellStyle, // Style given to characters as they're placed
+    row_extents: Vec<Option<(i32, i32)>>, // Leftmost and rightmost drawn column of each row
+    stale_extents: Vec<Option<(i32, i32)>>, // Columns cleared from the buffer but still showing on the terminal
+}
+
+/// Combines two column spans into one span covering both
+fn merge_extents(extent_a: Option<(i32, i32)>, extent_b: Option<(i32, i32)>) -> Option<(i32, i32)> {
+    match (extent_a, extent_b) {
+        (Some((left_a, right_a)), Some((left_b, right_b))) => Some((min(left_a, left_b), max(right_a, right_b))),
+        (Some(extent), None) | (None, Some(extent)) => Some(extent),
+        (None, None) => None,
+    }
+}
+
+impl FrameBuffer {
+    /// Creates a blank frame buffer covering a screen of the given size
+    pub fn with_dimensions(rows: i32, cols: i32) -> FrameBuffer {
+        let rows = max(rows, 0);
+        let cols = max(cols, 0);
+
+        FrameBuffer {
+            rows,
+            cols,
+            cells: vec![' '; rows as usize * cols as usize],
+            styles: vec![CellStyle::Plain; rows as usize * cols as usize],
+            current_style: CellStyle::Plain,
+            row_extents: vec![None; rows as usize],
+            stale_extents: vec![None; rows as usize],
+        }
+    }
+
+    /// The number of rows in the buffer
+    pub fn rows(&self) -> i32 {
+        self.rows
+    }
+    /// The number of columns in the buffer
+    pub fn cols(&self) -> i32 {
+        self.cols
+    }
+
+    /// Sets the style given to characters placed from now on, like curses' attrset
+    pub fn set_style(&mut self, style: CellStyle) {
+        self.current_style = style;
+    }
+
+    /// Places a character in the buffer with the current style. Characters which land off-screen
+    /// are ignored.
+    pub fn put_char(&mut self, row: i32, col: i32, fill_char: char) {
+        if row < 0 || row >= self.rows || col < 0 || col >= self.cols {
+            return;
+        }
+
+        let cell_idx = (row * self.cols + col) as usize;
+        self.cells[cell_idx] = fill_char;
+        self.styles[cell_idx] = self.current_style;
+        self.row_extents[row as usize] = merge_extents(self.row_extents[row as usize], Some((col, col)));
+    }
+
+    /// True if nothing has been drawn at the given position since the buffer was last cleared.
+    /// Off-screen positions are never blank.
+    pub fn is_blank(&self, row: i32, col: i32) -> bool {
+        if row < 0 || row >= self.rows || col < 0 || col >= self.cols {
+            return false;
+        }
+
+        let cell_idx = (row * self.cols + col) as usize;
+        return self.cells[cell_idx] == ' ' && self.styles[cell_idx] == CellStyle::Plain;
+    }
+
+    /// Places a string in the buffer from left to right with the current style
+    pub fn put_str(&mut self, row: i32, col: i32, text: &str) {
+        for (char_idx, text_char) in text.chars().enumerate() {
+            self.put_char(row, col + char_idx as i32, text_char);
+        }
+    }
+
+    /// Copies everything drawn into another buffer onto this one with its top left corner at the
+    /// given position, keeping each character's style. Anything falling outside this buffer is
+    /// cut off.
+    pub fn draw_buffer(&mut self, row_offset: i32, col_offset: i32, other: &FrameBuffer) {
+        let current_style = self.current_style;
+
+        for row in 0..other.rows {
+            if let Some((left, right)) = other.row_extents[row as usize] {
+                for col in left..=right {
+                    let cell_idx = (row * other.cols + col) as usize;
+                    self.current_style = other.styles[cell_idx];
+                    self.put_char(row + row_offset, col + col_offset, other.cells[cell_idx]);
+                }
+            }
+        }
+
+        self.current_style = current_style;
+    }
+
+    /// Gives everything drawn so far the given style, e.g. to dim a frame behind an overlay
+    pub fn restyle_drawn(&mut self, style: CellStyle) {
+        for row in 0..self.rows {
+            if let Some((left, right)) = self.row_extents[row as usize] {
+                for col in left..=right {
+                    self.styles[(row * self.cols + col) as usize] = style;
+                }
+            }
+        }
+    }
+
+    /// Blanks out everything drawn into the buffer. The cleared cells are remembered so the next
+    /// flush can erase them from the terminal without clearing the whole screen.
+    pub fn clear(&mut self) {
+        for row in 0..self.rows {
+            let row_idx = row as usize;
+
+            if let Some((left, right)) = self.row_extents[row_idx] {
+                for col in left..=right {
+                    let cell_idx = (row * self.cols + col) as usize;
+                    self.cells[cell_idx] = ' ';
+                    self.styles[cell_idx] = CellStyle::Plain;
+                }
+            }
+
+            self.stale_extents[row_idx] = merge_extents(self.stale_extents[row_idx], self.row_extents[row_idx]);
+            self.row_extents[row_idx] = None;
+        }
+    }
+
+    /// Sends every row that has been drawn on or needs erasing to curses, one call per run of
+    /// same-styled characters
+    pub fn flush(&mut self) {
+        self.drain_changed_runs(|row, col, style, run_text| {
+            attrset(style.curses_attr());
+            mvaddstr(row, col, run_text);
+        });
+        attrset(A_NORMAL());
+    }
+
+    /// The buffer's contents as plain text, one line per row with trailing blanks removed
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row_cells in self.cells.chunks(max(self.cols, 1) as usize) {
+            let row_text: String = row_cells.iter().collect();
+            text.push_str(row_text.trim_end());
+            text.push('\n');
+        }
+
+        return text;
+    }
+
+    /// A row of the buffer as text with ANSI styling, with trailing unstyled blanks removed
+    pub fn ansi_row(&self, row: i32) -> String {
+        let row_start = (row * self.cols) as usize;
+        let used_cols = (0..self.cols)
+            .rev()
+            .find(|col| {
+                let cell_idx = row_start + *col as usize;
+                self.cells[cell_idx] != ' ' || self.styles[cell_idx] != CellStyle::Plain
+            })
+            .map_or(0, |last_col| last_col + 1);
+
+        let mut output = String::new();
+        let mut active_style = CellStyle::Plain;
+        for (_, style, run_text) in self.style_runs(row, 0, used_cols - 1) {
+            push_styled_run(&mut output, &mut active_style, style, &run_text);
+        }
+        if active_style != CellStyle::Plain {
+            output.push_str(CellStyle::Plain.ansi_code());
+        }
+
+        return output;
+    }
+
+    /// Produces ANSI escape sequences which bring a terminal showing the last flushed frame up
+    /// to date with this one, for sending frames somewhere other than the local curses screen
+    pub fn flush_ansi(&mut self) -> String {
+        let mut output = String::new();
+        let mut active_style = CellStyle::Plain;
+        self.drain_changed_runs(|row, col, style, run_text| {
+            output.push_str(&format!("\x1b[{};{}H", row + 1, col + 1));
+            push_styled_run(&mut output, &mut active_style, style, run_text);
+        });
+        if active_style != CellStyle::Plain {
+            output.push_str(CellStyle::Plain.ansi_code());
+        }
+
+        return output;
+    }
+
+    /// Splits the columns from left to right of a row into runs of characters sharing a style,
+    /// giving the starting column, style, and text of each run
+    fn style_runs(&self, row: i32, left: i32, right: i32) -> Vec<(i32, CellStyle, String)> {
+        let mut runs: Vec<(i32, CellStyle, String)> = Vec::new();
+
+        for col in left..=right {
+            let cell_idx = (row * self.cols + col) as usize;
+            let (cell_char, cell_style) = (self.cells[cell_idx], self.styles[cell_idx]);
+
+            match runs.last_mut() {
+                Some((_, run_style, run_text)) if *run_style == cell_style => run_text.push(cell_char),
+                _ => runs.push((col, cell_style, cell_char.to_string())),
+            }
+        }
+
+        return runs;
+    }
+
+    /// Calls emit_run with the row, starting column, style, and text of every run of characters
+    /// that has been drawn on or needs erasing, then forgets the erased spans
+    fn drain_changed_runs(&mut self, mut emit_run: impl FnMut(i32, i32, CellStyle, &str)) {
+        for row in 0..self.rows {
+            let row_idx = row as usize;
+
+            if let Some((left, right)) = merge_extents(self.row_extents[row_idx], self.stale_extents[row_idx]) {
+                for (col, style, run_text) in self.style_runs(row, left, right) {
+                    emit_run(row, col, style, &run_text);
+                }
+            }
+
+            self.stale_extents[row_idx] = None;
+        }
+    }
+}
+
+/// Appends text to ANSI output, switching style first if it differs from the active one
+fn push_styled_run(output: &mut String, active_style: &mut CellStyle, style: CellStyle, run_text: &str) {
+    if style != *active_style {
+        // Reset first so attributes from the previous style don't carry over
+        output.push_str(CellStyle::Plain.ansi_code());
+        if style != CellStyle::Plain {
+            output.push_str(style.ansi_code());
+        }
+        *active_style = style;
+    }
+
+    output.push_str(run_text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CellStyle, FrameBuffer};
+
+    #[test]
+    fn ignores_offscreen_characters() {
+        let mut buffer = FrameBuffer::with_dimensions(2, 2);
+        buffer.put_char(-1, 0, '#');
+        buffer.put_char(0, 2, '#');
+
+        assert!(buffer.row_extents.iter().all(|extent| extent.is_none()));
+    }
+
+    #[test]
+    fn tracks_drawn_extent_per_row() {
+        let mut buffer = FrameBuffer::with_dimensions(2, 10);
+        buffer.put_char(1, 7, '#');
+        buffer.put_char(1, 2, '.');
+
+        assert_eq!(None, buffer.row_extents[0]);
+        assert_eq!(Some((2, 7)), buffer.row_extents[1]);
+    }
+
+    #[test]
+    fn clear_blanks_drawn_cells() {
+        let mut buffer = FrameBuffer::with_dimensions(1, 3);
+        buffer.put_char(0, 1, '#');
+        buffer.clear();
+
+        assert!(buffer.cells.iter().all(|cell| *cell == ' '));
+        assert_eq!(None, buffer.row_extents[0]);
+    }
+
+    #[test]
+    fn clear_remembers_cells_to_erase() {
+        let mut buffer = FrameBuffer::with_dimensions(1, 10);
+        buffer.put_char(0, 1, '#');
+        buffer.put_char(0, 4, '#');
+        buffer.clear();
+        buffer.put_char(0, 6, '#');
+        buffer.clear();
+
+        assert_eq!(Some((1, 6)), buffer.stale_extents[0]);
+    }
+
+    #[test]
+    fn blank_until_drawn_on() {
+        let mut buffer = FrameBuffer::with_dimensions(2, 2);
+        buffer.set_style(CellStyle::Reverse);
+        buffer.put_char(0, 0, ' ');
+        buffer.put_char(0, 1, '#');
+
+        assert!(!buffer.is_blank(0, 0));
+        assert!(!buffer.is_blank(0, 1));
+        assert!(buffer.is_blank(1, 0));
+        assert!(!buffer.is_blank(2, 0));
+
+        buffer.clear();
+        assert!(buffer.is_blank(0, 1));
+    }
+
+    #[test]
+    fn draws_other_buffer_at_offset() {
+        let mut viewport = FrameBuffer::with_dimensions(2, 3);
+        viewport.set_style(CellStyle::Bold);
+        viewport.put_str(1, 0, "abc");
+        let mut screen = FrameBuffer::with_dimensions(3, 4);
+        screen.draw_buffer(1, 2, &viewport);
+
+        let mut expected = FrameBuffer::with_dimensions(3, 4);
+        expected.set_style(CellStyle::Bold);
+        expected.put_str(2, 2, "ab");
+        assert_eq!("\n\n  ab\n", screen.to_text());
+        assert_eq!(expected.ansi_row(2), screen.ansi_row(2));
+    }
+
+    #[test]
+    fn text_trims_each_row() {
+        let mut buffer = FrameBuffer::with_dimensions(2, 4);
+        buffer.put_char(0, 1, '#');
+
+        assert_eq!(" #\n\n", buffer.to_text());
+    }
+
+    #[test]
+    fn ansi_flush_positions_each_changed_row() {
+        let mut buffer = FrameBuffer::with_dimensions(3, 10);
+        buffer.put_char(0, 2, '#');
+        buffer.put_char(2, 4, '#');
+        buffer.put_char(2, 6, '.');
+
+        assert_eq!("\x1b[1;3H#\x1b[3;5H# .", buffer.flush_ansi());
+    }
+
+    #[test]
+    fn ansi_output_switches_styles_between_runs() {
+        let mut buffer = FrameBuffer::with_dimensions(1, 10);
+        buffer.set_style(CellStyle::Reverse);
+        buffer.put_char(0, 0, ' ');
+        buffer.put_char(0, 1, ' ');
+        buffer.set_style(CellStyle::Bold);
+        buffer.put_char(0, 2, '#');
+
+        assert_eq!("\x1b[0m\x1b[7m  \x1b[0m\x1b[1m#\x1b[0m", buffer.ansi_row(0));
+    }
+}
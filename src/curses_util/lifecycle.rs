@@ -1,20 +1,53 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use ncurses::*;
 
+/// Set when the process carries on after being stopped, e.g. by Ctrl+Z
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn note_resumed(_signal: libc::c_int) {
+    RESUMED.store(true, Ordering::SeqCst);
+}
+
 pub struct CursesHandle {}
 
 impl CursesHandle {
     pub fn create() -> CursesHandle {
+        // Use the environment's locale so non-ASCII drawing characters come out correctly
+        setlocale(LcCategory::all, "");
         initscr();
         cbreak();
         noecho();
         nodelay(stdscr(), true);
         keypad(stdscr(), true);
 
+        // Curses restores the screen itself when resumed after Ctrl+Z, but the game loop also
+        // needs to know so the time spent stopped isn't taken for a slow frame
+        let resume_handler: extern "C" fn(libc::c_int) = note_resumed;
+        unsafe {
+            libc::signal(libc::SIGCONT, resume_handler as libc::sighandler_t);
+        }
+
         return CursesHandle{}
     }
+
+    /// Hands the terminal back to the shell and stops the process, the same as pressing Ctrl+Z.
+    /// Returns once the process is resumed, with the screen and input modes restored.
+    pub fn suspend(&self) {
+        endwin();
+        unsafe {
+            libc::raise(libc::SIGTSTP);
+        }
+        refresh();
+    }
+
+    /// True if the process has been resumed after being stopped since this was last called
+    pub fn take_resumed(&self) -> bool {
+        RESUMED.swap(false, Ordering::SeqCst)
+    }
 }
 impl Drop for CursesHandle {
     fn drop(&mut self) {
         endwin();
     }
-}
\ No newline at end of file
+}
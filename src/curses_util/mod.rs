@@ -1,2 +1,4 @@
 pub mod lifecycle;
 pub mod draw_2d;
+pub mod frame_buffer;
+pub mod ansi_mirror;
@@ -0,0 +1,270 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::curses_util::draw_2d::TriangleFillErr;
+use super::render::RenderStats;
+
+/// The most render errors kept word for word in the report; the rest are only counted
+const MAX_RENDER_ERROR_SAMPLES: usize = 20;
+/// Frame times over one less than this many milliseconds all share the histogram's last bucket
+const HISTOGRAM_MAX_MS: usize = 1000;
+/// Upper ends of the histogram bins shown to the player and written to the report, in milliseconds.
+/// Each bin includes its upper end, and the frames slower than the last bound get a bin of their own.
+const HISTOGRAM_BIN_BOUNDS_MS: [usize; 6] = [8, 17, 34, 50, 100, 250];
+/// How many of the slowest frames are remembered
+const WORST_STALL_COUNT: usize = 5;
+
+/// Counts frames by how long they took to the millisecond, so percentiles can be read at any
+/// point in a run without keeping every frame time around
+pub struct FrameTimeHistogram {
+    bucket_counts: Vec<usize>, // Frames taking up to each whole number of milliseconds, and more than one less
+    frames: usize,
+    max_frame_time: Duration,
+}
+
+/// A range of frame times and how many frames fell in it
+#[derive(Debug, PartialEq, Serialize)]
+pub struct HistogramBin {
+    /// The range's upper end in milliseconds, or none for the frames slower than every other bin
+    pub up_to_ms: Option<usize>,
+    pub frames: usize,
+}
+
+/// One of the slowest frames of the run
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub struct Stall {
+    /// Which frame it was, counting from zero
+    pub frame: usize,
+    pub ms: f64,
+}
+
+impl FrameTimeHistogram {
+    pub fn new() -> FrameTimeHistogram {
+        FrameTimeHistogram { bucket_counts: vec![0; HISTOGRAM_MAX_MS + 1], frames: 0, max_frame_time: Duration::ZERO }
+    }
+
+    /// Counts a frame which took the given time
+    pub fn record(&mut self, frame_time: Duration) {
+        let bucket_idx = (frame_time.as_micros().div_ceil(1000) as usize).min(HISTOGRAM_MAX_MS);
+        self.bucket_counts[bucket_idx] += 1;
+        self.frames += 1;
+        self.max_frame_time = self.max_frame_time.max(frame_time);
+    }
+
+    /// The number of frames counted
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// The time in milliseconds the given fraction of frames finished within, rounded up to the
+    /// next whole millisecond but never past the slowest frame
+    pub fn percentile_ms(&self, percentile: f64) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+
+        let rank = ((percentile * self.frames as f64).ceil() as usize).clamp(1, self.frames);
+        let mut frames_so_far = 0;
+        for (bucket_idx, count) in self.bucket_counts.iter().enumerate() {
+            frames_so_far += count;
+            if frames_so_far >= rank && bucket_idx < HISTOGRAM_MAX_MS {
+                return (bucket_idx as f64).min(self.max_ms());
+            }
+        }
+
+        return self.max_ms();
+    }
+
+    /// The slowest frame time in milliseconds
+    pub fn max_ms(&self) -> f64 {
+        self.max_frame_time.as_secs_f64() * 1000.0
+    }
+
+    /// The frame counts grouped into a handful of ranges, fastest first
+    pub fn bins(&self) -> Vec<HistogramBin> {
+        let mut bins = Vec::new();
+        let mut bin_start = 0;
+
+        for bin_end in HISTOGRAM_BIN_BOUNDS_MS {
+            bins.push(HistogramBin { up_to_ms: Some(bin_end), frames: self.bucket_counts[bin_start..=bin_end].iter().sum() });
+            bin_start = bin_end + 1;
+        }
+        bins.push(HistogramBin { up_to_ms: None, frames: self.bucket_counts[bin_start..].iter().sum() });
+
+        return bins;
+    }
+}
+
+/// Collects figures over the course of a run for a report written when the game ends
+pub struct RunDiagnostics {
+    wall_count: usize,
+    seed: u64,
+    histogram: FrameTimeHistogram,
+    worst_stalls: Vec<Stall>, // Slowest first
+    total_walls_drawn: usize,
+    max_walls_drawn: usize,
+    render_error_count: usize,
+    render_error_samples: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct FrameTimePercentiles {
+    p50_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Serialize)]
+struct DiagnosticsReport<'d> {
+    wall_count: usize,
+    /// The seed the run's random choices came from, so the run can be repeated
+    seed: u64,
+    frames: usize,
+    frame_time: FrameTimePercentiles,
+    frame_time_histogram: Vec<HistogramBin>,
+    worst_stalls: &'d [Stall],
+    mean_walls_drawn: f64,
+    max_walls_drawn: usize,
+    render_error_count: usize,
+    render_errors: &'d [String],
+}
+
+impl RunDiagnostics {
+    /// Starts collecting diagnostics for a run with the given seed in a world with the given number of walls
+    pub fn new(wall_count: usize, seed: u64) -> RunDiagnostics {
+        RunDiagnostics {
+            wall_count,
+            seed,
+            histogram: FrameTimeHistogram::new(),
+            worst_stalls: Vec::new(),
+            total_walls_drawn: 0,
+            max_walls_drawn: 0,
+            render_error_count: 0,
+            render_error_samples: Vec::new(),
+        }
+    }
+
+    /// Adds a frame's figures and any errors hit while drawing it
+    pub fn record_frame(&mut self, stats: &RenderStats, render_errors: Vec<TriangleFillErr>) {
+        let stall = Stall { frame: self.histogram.frames(), ms: stats.last_frame_time.as_secs_f64() * 1000.0 };
+        let stall_idx = self.worst_stalls.iter().position(|worse_stall| worse_stall.ms < stall.ms).unwrap_or(self.worst_stalls.len());
+        if stall_idx < WORST_STALL_COUNT {
+            self.worst_stalls.insert(stall_idx, stall);
+            self.worst_stalls.truncate(WORST_STALL_COUNT);
+        }
+
+        self.histogram.record(stats.last_frame_time);
+        self.total_walls_drawn += stats.walls_drawn;
+        self.max_walls_drawn = self.max_walls_drawn.max(stats.walls_drawn);
+        self.render_error_count += render_errors.len();
+
+        let samples_left = MAX_RENDER_ERROR_SAMPLES.saturating_sub(self.render_error_samples.len());
+        self.render_error_samples.extend(render_errors.iter().take(samples_left).map(|err| format!("{:?}", err)));
+    }
+
+    /// Frame times so far, grouped by how long they took
+    pub fn frame_time_histogram(&self) -> &FrameTimeHistogram {
+        &self.histogram
+    }
+
+    /// The slowest frames so far, slowest first
+    pub fn worst_stalls(&self) -> &[Stall] {
+        &self.worst_stalls
+    }
+
+    /// Writes the report as JSON
+    pub fn write_report(&self, path: &Path) -> io::Result<()> {
+        let report = DiagnosticsReport {
+            wall_count: self.wall_count,
+            seed: self.seed,
+            frames: self.histogram.frames(),
+            frame_time: frame_time_percentiles(&self.histogram),
+            frame_time_histogram: self.histogram.bins(),
+            worst_stalls: &self.worst_stalls,
+            mean_walls_drawn: self.total_walls_drawn as f64 / self.histogram.frames().max(1) as f64,
+            max_walls_drawn: self.max_walls_drawn,
+            render_error_count: self.render_error_count,
+            render_errors: &self.render_error_samples,
+        };
+
+        return fs::write(path, serde_json::to_string_pretty(&report)?);
+    }
+}
+
+/// Reads the report's percentiles from the histogram, so they match the ones shown in game
+fn frame_time_percentiles(histogram: &FrameTimeHistogram) -> FrameTimePercentiles {
+    return FrameTimePercentiles {
+        p50_ms: histogram.percentile_ms(0.50),
+        p95_ms: histogram.percentile_ms(0.95),
+        p99_ms: histogram.percentile_ms(0.99),
+        max_ms: histogram.max_ms(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{frame_time_percentiles, FrameTimeHistogram, FrameTimePercentiles, HistogramBin, RunDiagnostics};
+    use crate::render::RenderStats;
+
+    #[test]
+    fn picks_nearest_rank_percentiles() {
+        let mut histogram = FrameTimeHistogram::new();
+        for frame_ms in (1..=100).rev() {
+            histogram.record(Duration::from_millis(frame_ms));
+        }
+
+        assert_eq!(FrameTimePercentiles { p50_ms: 50.0, p95_ms: 95.0, p99_ms: 99.0, max_ms: 100.0 }, frame_time_percentiles(&histogram));
+    }
+
+    #[test]
+    fn handles_no_frames() {
+        assert_eq!(0.0, frame_time_percentiles(&FrameTimeHistogram::new()).max_ms);
+    }
+
+    #[test]
+    fn histogram_percentiles_round_up_to_the_millisecond() {
+        let mut histogram = FrameTimeHistogram::new();
+        for frame_ms in 1..=100 {
+            histogram.record(Duration::from_micros(frame_ms * 1000 - 500));
+        }
+        histogram.record(Duration::from_secs(5));
+
+        assert_eq!(51.0, histogram.percentile_ms(0.5));
+        assert_eq!(5000.0, histogram.percentile_ms(1.0));
+        assert_eq!(HistogramBin { up_to_ms: None, frames: 1 }, histogram.bins()[6]);
+        assert_eq!(HistogramBin { up_to_ms: Some(8), frames: 8 }, histogram.bins()[0]);
+    }
+
+    #[test]
+    fn keeps_worst_stalls_slowest_first() {
+        let mut diagnostics = RunDiagnostics::new(0, 0);
+        for frame_ms in [30, 90, 10, 60, 20, 80, 70] {
+            let stats = RenderStats { last_frame_time: Duration::from_millis(frame_ms), ..RenderStats::default() };
+            diagnostics.record_frame(&stats, Vec::new());
+        }
+
+        let stalls: Vec<(usize, f64)> = diagnostics.worst_stalls().iter().map(|stall| (stall.frame, stall.ms)).collect();
+        assert_eq!(vec![(1, 90.0), (5, 80.0), (6, 70.0), (3, 60.0), (0, 30.0)], stalls);
+    }
+
+    #[test]
+    fn writes_seed_and_frames_to_report() {
+        let mut diagnostics = RunDiagnostics::new(3, 42);
+        diagnostics.record_frame(&RenderStats { last_frame_time: Duration::from_millis(10), ..RenderStats::default() }, Vec::new());
+        let path = std::env::temp_dir().join(format!("cursed-maze-diagnostics-test-{}.json", std::process::id()));
+        diagnostics.write_report(&path).unwrap();
+
+        let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(42, report["seed"]);
+        assert_eq!(3, report["wall_count"]);
+        assert_eq!(1, report["frames"]);
+    }
+}
@@ -1,4 +1,4 @@
-use std::f64::consts::FRAC_PI_2;
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
 
 use device_query::{DeviceQuery, DeviceState, Keycode};
 use ncurses::getch;
@@ -6,34 +6,586 @@ use ncurses::getch;
 use super::render::RENDER_FPS;
 use super::world::camera::Camera;
 
-#[derive(Eq, PartialEq)]
+/// World units the camera moves forward or back per second
+pub const MOVE_SPEED: f64 = 4.0;
+/// Radians the camera turns per second
+pub const TURN_SPEED: f64 = FRAC_PI_2;
+/// Radians the camera turns per key press when snap turning
+pub const SNAP_TURN_ANGLE: f64 = FRAC_PI_4;
+/// How much fine control scales down movement and turning
+const FINE_CONTROL_SCALE: f64 = 0.25;
+/// The most frames of movement a macro can hold, so macros stay short
+const MAX_MACRO_FRAMES: usize = 10 * RENDER_FPS as usize;
+/// How many camera positions can be quick saved at once
+pub const SAVE_SLOTS: usize = 3;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum ProgramCommand {
     NoCommand,
     Quit,
+    Screenshot,
+    ToggleHelp,
+    ToggleDebug,
+    ToggleDiagnostics,
+    ToggleMap,
+    ToggleFrameStep,
+    StepFrame,
+    ToggleFlashlight,
+    /// Save or load the camera position in a quick save slot, numbered from zero
+    SaveState(usize),
+    LoadState(usize),
+    TogglePhotoMode,
+    Suspend,
+}
+
+/// Something a key can be bound to
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    TurnLeft,
+    TurnRight,
+    Quit,
+    Screenshot,
+    ToggleHelp,
+    ToggleDebug,
+    ToggleDiagnostics,
+    ToggleMap,
+    ToggleFrameStep,
+    StepFrame,
+    ToggleFlashlight,
+    RecordMacro,
+    PlayMacro,
+    SaveState(usize),
+    LoadState(usize),
+    TogglePhotoMode,
+    Suspend,
+}
+
+impl Action {
+    /// Every action, in the order they're listed to the player
+    pub const ALL: [Action; 23] = [
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::ToggleMap,
+        Action::ToggleFlashlight,
+        Action::RecordMacro,
+        Action::PlayMacro,
+        Action::SaveState(0),
+        Action::SaveState(1),
+        Action::SaveState(2),
+        Action::LoadState(0),
+        Action::LoadState(1),
+        Action::LoadState(2),
+        Action::TogglePhotoMode,
+        Action::Screenshot,
+        Action::Suspend,
+        Action::ToggleHelp,
+        Action::ToggleDebug,
+        Action::ToggleDiagnostics,
+        Action::ToggleFrameStep,
+        Action::StepFrame,
+        Action::Quit,
+    ];
+
+    /// A short description of the action for the player
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::MoveForward => "Move forward",
+            Action::MoveBack => "Move back",
+            Action::TurnLeft => "Turn left",
+            Action::TurnRight => "Turn right",
+            Action::Quit => "Quit",
+            Action::Screenshot => "Save screenshot",
+            Action::ToggleHelp => "Show/hide help",
+            Action::ToggleDebug => "Show/hide debug info",
+            Action::ToggleDiagnostics => "Show/hide frame times",
+            Action::ToggleMap => "Switch map/first person",
+            Action::ToggleFrameStep => "Frame step mode on/off",
+            Action::StepFrame => "Advance one frame",
+            Action::ToggleFlashlight => "Flashlight on/off",
+            Action::RecordMacro => "Start/stop macro",
+            Action::PlayMacro => "Play macro",
+            Action::SaveState(_) => "Quick save position",
+            Action::LoadState(_) => "Quick load position",
+            Action::TogglePhotoMode => "Photo mode on/off",
+            Action::Suspend => "Suspend to shell",
+        }
+    }
+
+    /// The command the action issues when its key is first pressed, if it issues one
+    fn command(self) -> Option<ProgramCommand> {
+        match self {
+            Action::Quit => Some(ProgramCommand::Quit),
+            Action::Screenshot => Some(ProgramCommand::Screenshot),
+            Action::ToggleHelp => Some(ProgramCommand::ToggleHelp),
+            Action::ToggleDebug => Some(ProgramCommand::ToggleDebug),
+            Action::ToggleDiagnostics => Some(ProgramCommand::ToggleDiagnostics),
+            Action::ToggleMap => Some(ProgramCommand::ToggleMap),
+            Action::ToggleFrameStep => Some(ProgramCommand::ToggleFrameStep),
+            Action::StepFrame => Some(ProgramCommand::StepFrame),
+            Action::ToggleFlashlight => Some(ProgramCommand::ToggleFlashlight),
+            Action::SaveState(slot) => Some(ProgramCommand::SaveState(slot)),
+            Action::LoadState(slot) => Some(ProgramCommand::LoadState(slot)),
+            Action::TogglePhotoMode => Some(ProgramCommand::TogglePhotoMode),
+            Action::Suspend => Some(ProgramCommand::Suspend),
+            _ => None,
+        }
+    }
+}
+
+/// Links a key to the action it performs
+#[derive(Clone, Debug, PartialEq)]
+pub struct Binding {
+    pub key: Keycode,
+    pub action: Action,
+}
+
+/// The keys the game uses out of the box
+pub fn default_bindings() -> Vec<Binding> {
+    let binding = |key, action| Binding { key, action };
+
+    vec![
+        binding(Keycode::W, Action::MoveForward),
+        binding(Keycode::Up, Action::MoveForward),
+        binding(Keycode::S, Action::MoveBack),
+        binding(Keycode::Down, Action::MoveBack),
+        binding(Keycode::A, Action::TurnLeft),
+        binding(Keycode::Left, Action::TurnLeft),
+        binding(Keycode::D, Action::TurnRight),
+        binding(Keycode::Right, Action::TurnRight),
+        binding(Keycode::Escape, Action::Quit),
+        binding(Keycode::Q, Action::Quit),
+        binding(Keycode::M, Action::ToggleMap),
+        binding(Keycode::F, Action::ToggleFlashlight),
+        binding(Keycode::R, Action::RecordMacro),
+        binding(Keycode::T, Action::PlayMacro),
+        binding(Keycode::P, Action::Screenshot),
+        binding(Keycode::F1, Action::ToggleHelp),
+        binding(Keycode::Slash, Action::ToggleHelp),
+        binding(Keycode::F3, Action::ToggleDebug),
+        binding(Keycode::F4, Action::ToggleDiagnostics),
+        binding(Keycode::F5, Action::ToggleFrameStep),
+        binding(Keycode::F6, Action::SaveState(0)),
+        binding(Keycode::F7, Action::SaveState(1)),
+        binding(Keycode::F8, Action::SaveState(2)),
+        binding(Keycode::Key1, Action::LoadState(0)),
+        binding(Keycode::Key2, Action::LoadState(1)),
+        binding(Keycode::Key3, Action::LoadState(2)),
+        binding(Keycode::F2, Action::TogglePhotoMode),
+        binding(Keycode::F10, Action::Suspend),
+        binding(Keycode::Dot, Action::StepFrame),
+    ]
+}
+
+/// The default bindings with some keys swapped for others. Keys swapped for nothing are unbound.
+fn swapped_default_bindings(key_swaps: &[(Keycode, Option<Keycode>)]) -> Vec<Binding> {
+    return default_bindings()
+        .into_iter()
+        .filter_map(|binding| match key_swaps.iter().find(|(from_key, _)| *from_key == binding.key) {
+            Some((_, to_key)) => to_key.clone().map(|key| Binding { key, action: binding.action }),
+            None => Some(binding),
+        })
+        .collect();
+}
+
+/// Keys for the right hand, leaving the left hand free for a mouse or other device
+fn left_handed_bindings() -> Vec<Binding> {
+    swapped_default_bindings(&[
+        (Keycode::W, Some(Keycode::P)),
+        (Keycode::S, Some(Keycode::Semicolon)),
+        (Keycode::A, Some(Keycode::L)),
+        (Keycode::D, Some(Keycode::Apostrophe)),
+        (Keycode::Q, None),
+        (Keycode::F, Some(Keycode::Comma)),
+        (Keycode::R, Some(Keycode::Key9)),
+        (Keycode::T, Some(Keycode::Key0)),
+        (Keycode::P, Some(Keycode::O)),
+    ])
+}
+
+/// The default keys with movement on IJKL instead of WASD
+fn ijkl_bindings() -> Vec<Binding> {
+    swapped_default_bindings(&[
+        (Keycode::W, Some(Keycode::I)),
+        (Keycode::S, Some(Keycode::K)),
+        (Keycode::A, Some(Keycode::J)),
+        (Keycode::D, Some(Keycode::L)),
+    ])
+}
+
+/// A complete set of key bindings for a common keyboard layout
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BindingProfile {
+    #[default]
+    Default,
+    LeftHanded,
+    Ijkl,
+}
+
+impl BindingProfile {
+    /// Looks up one of the built-in profiles by name
+    pub fn named(name: &str) -> Option<BindingProfile> {
+        match name {
+            "default" => Some(BindingProfile::Default),
+            "left-handed" => Some(BindingProfile::LeftHanded),
+            "ijkl" => Some(BindingProfile::Ijkl),
+            _ => None,
+        }
+    }
+
+    /// The name the profile is chosen by
+    pub fn name(self) -> &'static str {
+        match self {
+            BindingProfile::Default => "default",
+            BindingProfile::LeftHanded => "left-handed",
+            BindingProfile::Ijkl => "ijkl",
+        }
+    }
+
+    /// The bindings making up the profile
+    pub fn bindings(self) -> Vec<Binding> {
+        match self {
+            BindingProfile::Default => default_bindings(),
+            BindingProfile::LeftHanded => left_handed_bindings(),
+            BindingProfile::Ijkl => ijkl_bindings(),
+        }
+    }
+}
+
+/// Keys for the player on the left of a shared keyboard, who also gets to quit
+pub fn player_one_bindings() -> Vec<Binding> {
+    let binding = |key, action| Binding { key, action };
+
+    vec![
+        binding(Keycode::W, Action::MoveForward),
+        binding(Keycode::S, Action::MoveBack),
+        binding(Keycode::A, Action::TurnLeft),
+        binding(Keycode::D, Action::TurnRight),
+        binding(Keycode::Escape, Action::Quit),
+        binding(Keycode::Q, Action::Quit),
+    ]
+}
+
+/// Keys for the player on the right of a shared keyboard
+pub fn player_two_bindings() -> Vec<Binding> {
+    let binding = |key, action| Binding { key, action };
+
+    vec![
+        binding(Keycode::Up, Action::MoveForward),
+        binding(Keycode::Down, Action::MoveBack),
+        binding(Keycode::Left, Action::TurnLeft),
+        binding(Keycode::Right, Action::TurnRight),
+    ]
+}
+
+/// How the turn keys rotate the camera
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TurnMode {
+    /// Turn continuously while the key is held
+    Smooth,
+    /// Turn a fixed angle once per key press, for players sensitive to motion
+    Snap,
+}
+
+/// Tracks keys across frames so presses can be told apart from held keys
+pub struct InputState {
+    bindings: Vec<Binding>,
+    turn_mode: TurnMode,
+    speed_multiplier: f64,
+    fine_control: bool,
+    previous_keys: Vec<Keycode>,
+    macro_recording: Option<Vec<(f64, f64)>>, // Forward and angle change of each frame recorded so far
+    recorded_macro: Vec<(f64, f64)>,
+    macro_finished: bool, // Set when a recording ends, until the new macro is taken for saving
+    macro_playback: Option<usize>, // Index of the next recorded frame to play
+    macro_step: MacroStep,
+}
+
+/// What the macro did with the most recent frame, so it can be undone if the frame didn't happen
+#[derive(Copy, Clone)]
+enum MacroStep {
+    Idle,
+    Recorded,
+    Played(usize),
+}
+
+impl InputState {
+    pub fn new(bindings: Vec<Binding>, turn_mode: TurnMode) -> InputState {
+        InputState {
+            bindings,
+            turn_mode,
+            speed_multiplier: 1.0,
+            fine_control: false,
+            previous_keys: Vec::new(),
+            macro_recording: None,
+            recorded_macro: Vec::new(),
+            macro_finished: false,
+            macro_playback: None,
+            macro_step: MacroStep::Idle,
+        }
+    }
+
+    /// Scales how fast the movement keys move the camera
+    pub fn set_speed_multiplier(&mut self, speed_multiplier: f64) {
+        self.speed_multiplier = speed_multiplier;
+    }
+
+    /// Slows movement and turning right down for lining things up precisely, or restores them
+    pub fn set_fine_control(&mut self, fine_control: bool) {
+        self.fine_control = fine_control;
+    }
+
+    /// The key bindings currently in use
+    pub fn bindings(&self) -> &[Binding] {
+        &self.bindings
+    }
+
+    /// Replaces the macro played by the play key, e.g. with one saved by an earlier game
+    pub fn set_recorded_macro(&mut self, recorded_macro: Vec<(f64, f64)>) {
+        self.recorded_macro = recorded_macro;
+    }
+
+    /// The macro the player just finished recording, once per recording
+    pub fn take_new_macro(&mut self) -> Option<&[(f64, f64)]> {
+        if !self.macro_finished {
+            return None;
+        }
+        self.macro_finished = false;
+
+        return Some(&self.recorded_macro);
+    }
+
+    /// True while the player's movement is being recorded into a macro
+    pub fn recording_macro(&self) -> bool {
+        self.macro_recording.is_some()
+    }
+
+    /// Takes back the macro's progress from the last frame, for frames where the world stood still
+    /// and the camera didn't move. The frame being played is played again next frame, and a
+    /// frame being recorded is dropped.
+    pub fn hold_macro_frame(&mut self) {
+        match self.macro_step {
+            MacroStep::Idle => {}
+            MacroStep::Recorded => {
+                if let Some(recording) = &mut self.macro_recording {
+                    recording.pop();
+                }
+            }
+            MacroStep::Played(frame_idx) => self.macro_playback = Some(frame_idx),
+        }
+        self.macro_step = MacroStep::Idle;
+    }
+
+    /// Based on the state of the input device, move the camera accordingly.
+    ///
+    /// Returns the updated camera and the command the player gave, if any.
+    pub fn move_camera(&mut self, input: &DeviceState, camera_entity: &Camera) -> (Camera, ProgramCommand) {
+        // Consume input so it's not redirected to the terminal
+        getch();
+
+        return self.apply_keys(input.get_keys(), camera_entity);
+    }
+
+    /// Moves the camera according to the keys held down this frame
+    fn apply_keys(&mut self, keys_pressed: Vec<Keycode>, camera_entity: &Camera) -> (Camera, ProgramCommand) {
+        let mut command = ProgramCommand::NoCommand;
+        let mut forward_change = 0.0;
+        let mut angle_change = 0.0;
+        let mut moved = false;
+        let mut macro_toggled = false;
+        let mut macro_started = false;
+        let fine_scale = if self.fine_control { FINE_CONTROL_SCALE } else { 1.0 };
+        let move_change = fine_scale * self.speed_multiplier * MOVE_SPEED / RENDER_FPS;
+
+        for binding in &self.bindings {
+            if !keys_pressed.contains(&binding.key) {
+                continue;
+            }
+
+            let newly_pressed = !self.previous_keys.contains(&binding.key);
+            let turn_change = fine_scale * match self.turn_mode {
+                TurnMode::Smooth => TURN_SPEED / RENDER_FPS,
+                TurnMode::Snap if newly_pressed => SNAP_TURN_ANGLE,
+                TurnMode::Snap => 0.0,
+            };
+
+            match binding.action {
+                Action::MoveForward => forward_change += move_change,
+                Action::MoveBack => forward_change -= move_change,
+                Action::TurnLeft => angle_change += turn_change,
+                Action::TurnRight => angle_change -= turn_change,
+                Action::RecordMacro => macro_toggled |= newly_pressed,
+                Action::PlayMacro => macro_started |= newly_pressed,
+                // Commands only fire once per press rather than every frame the key is held
+                action => {
+                    if let (true, Some(action_command)) = (newly_pressed, action.command()) {
+                        command = action_command;
+                    }
+                }
+            }
+            moved |= matches!(binding.action, Action::MoveForward | Action::MoveBack | Action::TurnLeft | Action::TurnRight);
+        }
+
+        self.previous_keys = keys_pressed;
+        let (forward_change, angle_change) = self.apply_macro(forward_change, angle_change, moved, macro_toggled, macro_started);
+
+        return (camera_entity.update_cam(forward_change, angle_change), command);
+    }
+
+    /// Records this frame's movement into the macro being recorded, or swaps it for the next frame
+    /// of the macro being played. Moving by hand stops a macro playing.
+    fn apply_macro(&mut self, forward_change: f64, angle_change: f64, moved: bool, macro_toggled: bool, macro_started: bool) -> (f64, f64) {
+        self.macro_step = MacroStep::Idle;
+        if macro_toggled {
+            self.macro_playback = None;
+            match self.macro_recording.take() {
+                Some(recording) => {
+                    self.recorded_macro = trim_idle_frames(recording);
+                    self.macro_finished = true;
+                }
+                None => self.macro_recording = Some(Vec::new()),
+            }
+            return (forward_change, angle_change);
+        }
+
+        if let Some(recording) = &mut self.macro_recording {
+            recording.push((forward_change, angle_change));
+            self.macro_step = MacroStep::Recorded;
+            if recording.len() >= MAX_MACRO_FRAMES {
+                self.recorded_macro = trim_idle_frames(self.macro_recording.take().unwrap_or_default());
+                self.macro_finished = true;
+            }
+            return (forward_change, angle_change);
+        }
+
+        if macro_started && !self.recorded_macro.is_empty() {
+            self.macro_playback = Some(0);
+        } else if moved {
+            self.macro_playback = None;
+        }
+
+        let frame_idx = match self.macro_playback {
+            Some(frame_idx) => frame_idx,
+            None => return (forward_change, angle_change),
+        };
+        self.macro_playback = Some(frame_idx + 1).filter(|next_idx| *next_idx < self.recorded_macro.len());
+        self.macro_step = MacroStep::Played(frame_idx);
+
+        return self.recorded_macro[frame_idx];
+    }
+}
+
+/// Drops the frames without any movement from the start and end of a recorded macro
+fn trim_idle_frames(mut recording: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let is_idle = |frame: &(f64, f64)| *frame == (0.0, 0.0);
+
+    while recording.last().is_some_and(is_idle) {
+        recording.pop();
+    }
+    let first_moving_frame = recording.iter().position(|frame| !is_idle(frame)).unwrap_or(recording.len());
+    recording.drain(..first_moving_frame);
+
+    return recording;
 }
 
-/// Based on the state of the input device, move the camera accordingly.
-///
-/// Returns the updated camera and a boolean saying whether or not the program should be quit.
-pub fn move_camera(input: &DeviceState, camera_entity: &Camera) -> (Camera, ProgramCommand) {
-    let keys_pressed = input.get_keys();
-    let mut command = ProgramCommand::NoCommand;
-    let mut forward_change = 0.0;
-    let mut angle_change = 0.0;
+#[cfg(test)]
+mod tests {
+    use device_query::Keycode;
+
+    use super::{default_bindings, Action, BindingProfile, InputState, ProgramCommand, TurnMode, SAVE_SLOTS, SNAP_TURN_ANGLE};
+    use crate::world::camera::Camera;
+
+    #[test]
+    fn commands_fire_once_per_press() {
+        let mut input = InputState::new(default_bindings(), TurnMode::Smooth);
+        let (cam, first_command) = input.apply_keys(vec![Keycode::F1], &Camera::new());
+        let (_, second_command) = input.apply_keys(vec![Keycode::F1], &cam);
+
+        assert_eq!(ProgramCommand::ToggleHelp, first_command);
+        assert_eq!(ProgramCommand::NoCommand, second_command);
+    }
+
+    #[test]
+    fn each_save_slot_has_save_and_load_keys() {
+        let mut input = InputState::new(default_bindings(), TurnMode::Smooth);
+        let slot_keys = [(Keycode::F6, Keycode::Key1), (Keycode::F7, Keycode::Key2), (Keycode::F8, Keycode::Key3)];
+        assert_eq!(SAVE_SLOTS, slot_keys.len());
 
-    // Consume input so it's not redirected to the terminal
-    getch();
+        for (slot, (save_key, load_key)) in slot_keys.iter().enumerate() {
+            let (cam, save_command) = input.apply_keys(vec![save_key.clone()], &Camera::new());
+            let (cam, _) = input.apply_keys(vec![], &cam);
+            let (cam, load_command) = input.apply_keys(vec![load_key.clone()], &cam);
+            input.apply_keys(vec![], &cam);
 
-    for key in keys_pressed {
-        match key {
-            Keycode::W | Keycode::Up => forward_change = forward_change + 4.0 / RENDER_FPS,
-            Keycode::S | Keycode::Down => forward_change = forward_change - 4.0 / RENDER_FPS,
-            Keycode::A | Keycode::Left => angle_change = angle_change + FRAC_PI_2 / RENDER_FPS,
-            Keycode::D | Keycode::Right => angle_change = angle_change - FRAC_PI_2 / RENDER_FPS,
-            Keycode::Escape | Keycode::Q => command = ProgramCommand::Quit,
-            _ => {},
+            assert_eq!(ProgramCommand::SaveState(slot), save_command, "slot {}", slot);
+            assert_eq!(ProgramCommand::LoadState(slot), load_command, "slot {}", slot);
         }
     }
 
-    return (camera_entity.update_cam(forward_change, angle_change), command);
+    #[test]
+    fn snap_turns_once_per_press() {
+        let mut input = InputState::new(default_bindings(), TurnMode::Snap);
+        let (cam, _) = input.apply_keys(vec![Keycode::A], &Camera::new());
+        let (cam, _) = input.apply_keys(vec![Keycode::A], &cam);
+
+        assert_eq!(SNAP_TURN_ANGLE, cam.facing_direction());
+    }
+
+    #[test]
+    fn profiles_bind_every_action_to_distinct_keys() {
+        for profile in [BindingProfile::Default, BindingProfile::LeftHanded, BindingProfile::Ijkl] {
+            let bindings = profile.bindings();
+
+            for action in Action::ALL.iter() {
+                assert!(bindings.iter().any(|binding| binding.action == *action), "{:?} has nothing bound to {:?}", profile, action);
+            }
+            for binding in &bindings {
+                assert_eq!(1, bindings.iter().filter(|other| other.key == binding.key).count(), "{:?} binds {:?} twice", profile, binding.key);
+            }
+            assert_eq!(Some(profile), BindingProfile::named(profile.name()));
+        }
+    }
+
+    #[test]
+    fn replays_recorded_macro() {
+        let mut input = InputState::new(default_bindings(), TurnMode::Snap);
+        let cam = Camera::new();
+        input.apply_keys(vec![Keycode::R], &cam);
+        input.apply_keys(vec![], &cam);
+        input.apply_keys(vec![Keycode::A], &cam);
+        input.apply_keys(vec![], &cam);
+        input.apply_keys(vec![Keycode::A], &cam);
+        input.apply_keys(vec![Keycode::R], &cam);
+        assert!(!input.recording_macro());
+
+        let (cam, _) = input.apply_keys(vec![Keycode::T], &cam);
+        let (cam, _) = input.apply_keys(vec![], &cam);
+        let (cam, _) = input.apply_keys(vec![], &cam);
+        let (cam, _) = input.apply_keys(vec![], &cam);
+
+        assert!((2.0 * SNAP_TURN_ANGLE - cam.facing_direction()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn held_frames_keep_macro_in_place() {
+        let mut input = InputState::new(default_bindings(), TurnMode::Snap);
+        let cam = Camera::new();
+        input.apply_keys(vec![Keycode::R], &cam);
+        input.apply_keys(vec![Keycode::A], &cam);
+        input.apply_keys(vec![], &cam);
+        input.apply_keys(vec![Keycode::A], &cam);
+        input.apply_keys(vec![Keycode::R], &cam);
+
+        // The second frame of playback lands on a frozen frame, so it's played again afterwards
+        let (cam, _) = input.apply_keys(vec![Keycode::T], &cam);
+        input.apply_keys(vec![], &cam);
+        input.hold_macro_frame();
+        let (cam, _) = input.apply_keys(vec![], &cam);
+        let (cam, _) = input.apply_keys(vec![], &cam);
+
+        assert!((2.0 * SNAP_TURN_ANGLE - cam.facing_direction()).abs() < 1e-9);
+    }
 }
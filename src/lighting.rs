@@ -0,0 +1,65 @@
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// The dimmest the world gets in the middle of the night, where 1.0 is full daylight
+const NIGHT_LIGHT_LEVEL: f64 = 0.2;
+/// Radians either side of where the camera faces that the flashlight's beam reaches
+const FLASHLIGHT_HALF_ANGLE: f64 = PI / 16.0;
+/// The fraction of the surrounding light left on things outside the flashlight's beam
+const FLASHLIGHT_PERIPHERY_LIGHT: f64 = 0.5;
+
+/// A slow cycle of the light falling on the whole world, starting at midday
+pub struct DayNightCycle {
+    day_length: Duration,
+}
+
+impl DayNightCycle {
+    /// Creates a cycle which goes from midday through midnight and back over the given length of time
+    pub fn with_day_length(day_length: Duration) -> DayNightCycle {
+        DayNightCycle { day_length }
+    }
+
+    /// The light level, between the night level and 1.0, once the given time has passed
+    pub fn light_level(&self, elapsed: Duration) -> f64 {
+        if self.day_length.is_zero() {
+            return 1.0;
+        }
+
+        let time_of_day = elapsed.as_secs_f64() / self.day_length.as_secs_f64();
+        let daylight = 0.5 + 0.5 * (2.0 * PI * time_of_day).cos();
+
+        return NIGHT_LIGHT_LEVEL + (1.0 - NIGHT_LIGHT_LEVEL) * daylight;
+    }
+}
+
+/// The light falling on something at the given angle from where the camera faces while the
+/// flashlight is on. Things in the beam are fully lit and everything else is dimmed.
+pub fn flashlight_light_level(light_level: f64, angle_from_center: f64) -> f64 {
+    if angle_from_center.abs() <= FLASHLIGHT_HALF_ANGLE {
+        return 1.0;
+    }
+
+    return light_level * FLASHLIGHT_PERIPHERY_LIGHT;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{flashlight_light_level, DayNightCycle, NIGHT_LIGHT_LEVEL};
+
+    #[test]
+    fn darkens_towards_midnight_and_back() {
+        let cycle = DayNightCycle::with_day_length(Duration::from_secs(60));
+
+        assert_eq!(1.0, cycle.light_level(Duration::ZERO));
+        assert!((cycle.light_level(Duration::from_secs(30)) - NIGHT_LIGHT_LEVEL).abs() < 1e-9);
+        assert!((cycle.light_level(Duration::from_secs(60)) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn flashlight_lights_only_its_beam() {
+        assert_eq!(1.0, flashlight_light_level(0.2, 0.1));
+        assert_eq!(0.5, flashlight_light_level(1.0, -1.0));
+    }
+}
@@ -0,0 +1,50 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::input::BindingProfile;
+
+/// Where the macro recorded with the given binding profile is kept, in the user's config
+/// directory. Each profile gets its own macro since the keys it was recorded with differ.
+pub fn macro_path(profile: BindingProfile) -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+    return Some(config_dir.join("cursed-maze").join(format!("macro-{}.json", profile.name())));
+}
+
+/// Reads a saved macro, as the forward and angle change of each frame
+pub fn load_macro(path: &Path) -> io::Result<Vec<(f64, f64)>> {
+    return Ok(serde_json::from_str(&fs::read_to_string(path)?)?);
+}
+
+/// Saves a macro, creating the directory it goes in if needed
+pub fn save_macro(path: &Path, frames: &[(f64, f64)]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    return fs::write(path, serde_json::to_string(frames)?);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{load_macro, save_macro};
+
+    #[test]
+    fn saved_macro_loads_back() {
+        let dir = std::env::temp_dir().join(format!("cursed-maze-macro-test-{}", std::process::id()));
+        let path = dir.join("macro-default.json");
+        let frames = vec![(0.25, 0.0), (0.0, -0.5)];
+
+        save_macro(&path, &frames).unwrap();
+        let loaded = load_macro(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(frames, loaded);
+    }
+}
@@ -1,32 +1,137 @@
+#![allow(clippy::needless_return)]
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use device_query::DeviceState;
 use ncurses::*;
 
+use cast::CastRecorder;
+use clock::{Clock, SystemClock};
+use control::{ControlRequest, ControlResponse, ControlServer};
 use curses_util::lifecycle::CursesHandle;
-use input::{move_camera, ProgramCommand};
+use diagnostics::RunDiagnostics;
+use input::{InputState, ProgramCommand, TurnMode, SAVE_SLOTS};
+use lighting::DayNightCycle;
+use macro_store::{load_macro, macro_path, save_macro};
+use overlay::{draw_debug_overlay, draw_diagnostics_overlay, draw_enlarge_message, draw_help_overlay, draw_recording_indicator};
+use particles::ParticleField;
 use render::{frame_sleep, Scene};
+use screenshot::save_screenshot;
+use settings::{Mode, Settings, ThemeChoice, USAGE};
+use spectate::SpectatorServer;
+use theme::Theme;
 use world::camera::Camera;
+use world::collision::{move_with_collision, DEFAULT_BODY_RADIUS};
+use world::decal::Decal;
+use world::doorway::mark_doorways;
+use world::world_entity::WorldEntity;
 use world::pillar::{Pillar, Wall};
 
+mod cast;
+mod clock;
+mod control;
 mod curses_util;
+mod diagnostics;
 mod world;
 mod input;
+mod lighting;
+mod macro_store;
+mod map_view;
+mod overlay;
+mod particles;
 mod render;
+mod rng;
+mod screenshot;
+mod serve;
+mod settings;
+mod spectate;
+mod split_screen;
+mod theme;
 
+/// How much faster the camera moves in noclip mode
+const NOCLIP_SPEED_MULTIPLIER: f64 = 3.0;
+/// The smallest terminal the game is drawn in. Smaller terminals are asked to enlarge.
+const MIN_ROWS: i32 = 12;
+const MIN_COLS: i32 = 40;
+/// The fraction of the usual draw distance walls are drawn out to in hardcore mode
+const HARDCORE_DRAW_DISTANCE_SCALE: f64 = 0.6;
 
 fn main() {
-    // When the curses handle falls out of scope it'll turn off curses
-    let _curse_handle = CursesHandle::create();
+    let mut settings = match Settings::from_args(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(err) => {
+            eprintln!("Error: {}\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let seed = time_seed();
+    let theme = match &settings.theme {
+        Some(ThemeChoice::Surprise) => Some(Theme::surprise(seed)),
+        Some(ThemeChoice::File(path)) => match Theme::load(path) {
+            Ok(theme) => Some(theme),
+            Err(err) => {
+                eprintln!("Could not load theme {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    if let Some(theme) = theme {
+        settings.render_style = theme.apply_to(settings.render_style);
+    }
+    let pillar_sets = create_pillar_sets();
+    let walls = create_walls(&pillar_sets);
 
-    let mut max_row = 0;
-    let mut max_col = 0;
-    getmaxyx(stdscr(), &mut max_row, &mut max_col);
+    match settings.mode {
+        Mode::Play => {
+            let cast_recorder = settings.cast_path.as_ref().map(|path| match CastRecorder::create(path) {
+                Ok(recorder) => recorder,
+                Err(err) => {
+                    eprintln!("Could not create cast file {}: {}", path.display(), err);
+                    std::process::exit(1);
+                }
+            });
 
-    let input = DeviceState::new();
+            let spectator_server = settings.spectator_port.map(|port| match SpectatorServer::bind(port) {
+                Ok(server) => server,
+                Err(err) => {
+                    eprintln!("Could not accept spectators on port {}: {}", port, err);
+                    std::process::exit(1);
+                }
+            });
 
-    let scene = Scene::with_dimensions(max_row, max_col);
-    let mut cam = Camera::new();
+            let control_server = settings.control_socket_path.as_ref().map(|path| match ControlServer::bind(path) {
+                Ok(server) => server,
+                Err(err) => {
+                    eprintln!("Could not create control socket {}: {}", path.display(), err);
+                    std::process::exit(1);
+                }
+            });
+
+            let integrations = Integrations { cast_recorder, spectator_server, control_server };
+            let diagnostics = play(&walls, &settings, integrations, &SystemClock::new(), seed);
+
+            if let Some(path) = &settings.diagnostics_path {
+                if let Err(err) = diagnostics.write_report(path) {
+                    eprintln!("Could not write diagnostics to {}: {}", path.display(), err);
+                }
+            }
+        }
+        Mode::SplitScreen => split_screen::play_split_screen(&walls, &settings),
+        Mode::Serve { port } => {
+            if let Err(err) = serve::serve(port, &walls, settings.render_style) {
+                eprintln!("Could not serve on port {}: {}", port, err);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Builds the pillars making up the world, one list per connected run of walls
+fn create_pillar_sets() -> Vec<Vec<Pillar>> {
     let mut pillar_set_1: Vec<Pillar> = Vec::new();
     let mut pillar_set_2: Vec<Pillar> = Vec::new();
+    let mut pillar_set_3: Vec<Pillar> = Vec::new();
 
     // Pillar set 1 horizontal segment
     for x_coord in (0..=8).step_by(2) {
@@ -38,40 +143,277 @@ fn main() {
         pillar_set_1.push(Pillar::at(8.0, y_coord as f64))
     }
 
-    // Pillar set 2 horizontal segment
-    for x_coord in (0..=12).step_by(2) {
+    // Pillar set 2 horizontal segment, up to a doorway in the corridor's right wall
+    for x_coord in (0..=4).step_by(2) {
         pillar_set_2.push(Pillar::at(x_coord as f64, -2.0));
     }
 
-    // Pillar set 2 vertical segment
+    // Pillar set 3 horizontal segment, carrying on from the other side of the doorway
+    for x_coord in (6..=12).step_by(2) {
+        pillar_set_3.push(Pillar::at(x_coord as f64, -2.0));
+    }
+
+    // Pillar set 3 vertical segment
     for y_coord in (0..=10).step_by(2) {
-        pillar_set_2.push(Pillar::at(12.0, y_coord as f64));
+        pillar_set_3.push(Pillar::at(12.0, y_coord as f64));
     }
 
-    // Create all walls from pillars
+    return vec![pillar_set_1, pillar_set_2, pillar_set_3];
+}
+
+/// Links each run of pillars into walls
+fn create_walls(pillar_sets: &[Vec<Pillar>]) -> Vec<Wall<'_, '_>> {
     let mut walls: Vec<Wall> = Vec::new();
 
-    for pillar_idx in 0..(pillar_set_1.len() - 1) {
-        walls.push(Wall::from_pillars(pillar_set_1.get(pillar_idx).unwrap(), pillar_set_1.get(pillar_idx + 1).unwrap()));
+    for pillar_set in pillar_sets {
+        for pillar_idx in 0..(pillar_set.len() - 1) {
+            walls.push(Wall::from_pillars(pillar_set.get(pillar_idx).unwrap(), pillar_set.get(pillar_idx + 1).unwrap()));
+        }
     }
-    for pillar_idx in 0..(pillar_set_2.len() - 1) {
-        walls.push(Wall::from_pillars(pillar_set_2.get(pillar_idx).unwrap(), pillar_set_2.get(pillar_idx + 1).unwrap()));
+
+    // Point the way at the end of the first corridor, where it turns left
+    let corner_wall = walls.iter_mut().find(|wall| wall.pillar1().x_pos() == 12.0 && wall.pillar1().y_pos() == 0.0);
+    if let Some(corner_wall) = corner_wall {
+        corner_wall.set_decal(Decal::at("<-", 0.5));
     }
+    mark_doorways(&mut walls);
+
+    return walls;
+}
+
+/// Optional connections to the outside world which run alongside the local game
+struct Integrations {
+    cast_recorder: Option<CastRecorder>,
+    spectator_server: Option<SpectatorServer>,
+    control_server: Option<ControlServer>,
+}
+
+/// Runs the game on the local terminal until the player quits, returning diagnostics about the run.
+/// Time passes on the given clock and the seed picks anything random, so runs can be repeated.
+fn play(walls: &[Wall], settings: &Settings, mut integrations: Integrations, clock: &impl Clock, seed: u64) -> RunDiagnostics {
+    // When the curses handle falls out of scope it'll turn off curses
+    let curse_handle = CursesHandle::create();
+
+    let mut max_row = 0;
+    let mut max_col = 0;
+    getmaxyx(stdscr(), &mut max_row, &mut max_col);
+
+    let input = DeviceState::new();
+    let turn_mode = if settings.reduced_motion { TurnMode::Snap } else { TurnMode::Smooth };
+    let mut input_state = InputState::new(settings.binding_profile.bindings(), turn_mode);
+    if settings.noclip {
+        input_state.set_speed_multiplier(NOCLIP_SPEED_MULTIPLIER);
+    }
+    let saved_macro_path = macro_path(settings.binding_profile);
+    if let Some(saved_macro) = saved_macro_path.as_deref().and_then(|path| load_macro(path).ok()) {
+        input_state.set_recorded_macro(saved_macro);
+    }
+
+    // Noclip cameras have no body, so nothing stops them going through walls
+    let body_radius = if settings.noclip { None } else { Some(settings.body_radius.unwrap_or(DEFAULT_BODY_RADIUS)) };
 
-    walls.reverse();
+    let mut scene = Scene::with_dimensions(max_row, max_col);
+    scene.set_style(settings.render_style);
+    if let Some(cell_aspect) = settings.cell_aspect {
+        scene.set_cell_aspect(cell_aspect);
+    }
+    if settings.hardcore {
+        scene.set_draw_distance_scale(HARDCORE_DRAW_DISTANCE_SCALE);
+    }
+    let day_night_cycle = settings.day_length.map(DayNightCycle::with_day_length);
+    let mut particle_field = if settings.particles {
+        Some(ParticleField::with_seed(seed))
+    } else {
+        None
+    };
+    let mut cam = Camera::new();
+    let mut show_help = false;
+    let mut show_debug = false;
+    let mut show_diagnostics = false;
+    let mut show_map = false;
+    let mut frame_step = false;
+    let mut flashlight_on = false;
+    let mut saved_cams: [Option<Camera>; SAVE_SLOTS] = [None; SAVE_SLOTS];
+    let mut photo_cam: Option<Camera> = None; // Free camera flying around the paused world in photo mode
+    let mut diagnostics = RunDiagnostics::new(walls.len(), seed);
+    // Time that has passed in the world, which stands still while it's paused
+    let mut world_time = Duration::ZERO;
+    let mut last_frame_start = clock.elapsed();
 
     loop {
-        let (new_cam, command) = move_camera(&input, &cam);
-        cam = new_cam;
+        let frame_start = clock.elapsed();
+        let frame_length = frame_start - last_frame_start;
+        last_frame_start = frame_start;
+        let (new_cam, mut command) = input_state.move_camera(&input, &photo_cam.unwrap_or(cam));
+
+        if let (Some(new_macro), Some(path)) = (input_state.take_new_macro(), &saved_macro_path) {
+            if save_macro(path, new_macro).is_err() {
+                alert(settings);
+            }
+        }
+
+        // Curses picks up the new size when the terminal is resized, which is noticed while reading input
+        let (old_row, old_col) = (max_row, max_col);
+        getmaxyx(stdscr(), &mut max_row, &mut max_col);
+        if (max_row, max_col) != (old_row, old_col) {
+            scene.resize(max_row, max_col);
+            clear();
+        }
+
+        // Hold the game until the terminal is big enough to show it properly
+        if max_row < MIN_ROWS || max_col < MIN_COLS {
+            draw_enlarge_message(scene.frame_buffer(), MIN_ROWS, MIN_COLS);
+            scene.present();
+            frame_sleep(clock);
+            if command == ProgramCommand::Quit {
+                break;
+            }
+            // The game is held, so this frame shouldn't use up a macro either
+            input_state.hold_macro_frame();
+            continue;
+        }
+
+        // In frame step mode the world only moves on frames the player asks to advance
+        let world_advances = photo_cam.is_none() && (!frame_step || command == ProgramCommand::StepFrame);
+
+        if photo_cam.is_some() {
+            photo_cam = Some(new_cam);
+        } else if world_advances {
+            cam = body_radius.map_or(new_cam, |radius| move_with_collision(&cam, &new_cam, walls, radius));
+        } else {
+            // Frozen frames shouldn't use up a macro being played or recorded
+            input_state.hold_macro_frame();
+        }
+
+        if let Some(server) = &mut integrations.control_server {
+            server.poll(|request| match request {
+                ControlRequest::Camera => ControlResponse::Camera { x: cam.x_pos(), y: cam.y_pos(), facing: cam.facing_direction() },
+                // The player camera stays put while the world is paused for photo mode or frame stepping
+                ControlRequest::Move { .. } if !world_advances => ControlResponse::Error { error: String::from("the world is paused") },
+                ControlRequest::Move { forward, turn } => {
+                    let moved = cam.update_cam(forward, turn);
+                    cam = body_radius.map_or(moved, |radius| move_with_collision(&cam, &moved, walls, radius));
+                    ControlResponse::Ok { ok: true }
+                }
+                ControlRequest::Screenshot => match save_screenshot(scene.frame_buffer()) {
+                    Ok(path) => ControlResponse::Screenshot { path },
+                    Err(err) => ControlResponse::Error { error: err.to_string() },
+                },
+                ControlRequest::Quit => {
+                    command = ProgramCommand::Quit;
+                    ControlResponse::Ok { ok: true }
+                }
+            });
+        }
+
+        match command {
+            ProgramCommand::ToggleHelp => show_help = !show_help,
+            // Hardcore players get no map, no readout of where they are and no saved positions
+            ProgramCommand::ToggleDebug if !settings.hardcore => show_debug = !show_debug,
+            ProgramCommand::ToggleMap if !settings.hardcore => show_map = !show_map,
+            ProgramCommand::ToggleDiagnostics => show_diagnostics = !show_diagnostics,
+            ProgramCommand::ToggleFrameStep => frame_step = !frame_step,
+            ProgramCommand::SaveState(slot) if !settings.hardcore => saved_cams[slot] = Some(cam),
+            ProgramCommand::LoadState(slot) if !settings.hardcore => {
+                if let Some(saved_cam) = saved_cams[slot] {
+                    cam = saved_cam;
+                }
+            }
+            // A free camera would get hardcore players around the map ban
+            ProgramCommand::TogglePhotoMode if !settings.hardcore => {
+                photo_cam = if photo_cam.is_some() { None } else { Some(cam) };
+                input_state.set_fine_control(photo_cam.is_some());
+            }
+            ProgramCommand::Suspend => curse_handle.suspend(),
+            ProgramCommand::ToggleFlashlight => {
+                flashlight_on = !flashlight_on;
+                scene.set_flashlight(flashlight_on);
+            }
+            _ => {}
+        }
+
+        // Photo mode shows the paused world from the free camera with nothing drawn over it
+        let in_photo_mode = photo_cam.is_some();
+        let view_cam = photo_cam.unwrap_or(cam);
+
+        if world_advances {
+            world_time += frame_length;
+        }
+        if let Some(cycle) = &day_night_cycle {
+            scene.set_light_level(cycle.light_level(world_time));
+        }
+        if show_map && !in_photo_mode {
+            scene.draw_map(&cam, walls);
+        } else {
+            scene.draw_frame(&view_cam, walls);
+            if let Some(field) = &mut particle_field {
+                if world_advances {
+                    field.update(&cam);
+                }
+                scene.draw_particles(&view_cam, field.particles());
+            }
+        }
+        if show_debug && !in_photo_mode {
+            let stats = scene.stats();
+            let detail = scene.detail_level();
+            draw_debug_overlay(scene.frame_buffer(), &cam, &stats, detail);
+        }
+        if input_state.recording_macro() && !in_photo_mode {
+            draw_recording_indicator(scene.frame_buffer());
+        }
+        if show_diagnostics && !in_photo_mode {
+            draw_diagnostics_overlay(scene.frame_buffer(), &diagnostics);
+        }
+        if show_help && !in_photo_mode {
+            draw_help_overlay(scene.frame_buffer(), input_state.bindings());
+        }
+        scene.present();
 
-        scene.render_frame(&cam, &walls);
+        // Time spent stopped in the background says nothing about how fast frames are drawn
+        if !curse_handle.take_resumed() {
+            scene.record_frame_time(clock.elapsed() - frame_start);
+            let render_errors = scene.take_render_errors();
+            diagnostics.record_frame(&scene.stats(), render_errors);
+        }
+
+        if command == ProgramCommand::Screenshot && save_screenshot(scene.frame_buffer()).is_err() {
+            alert(settings);
+        }
+
+        // Stop recording rather than interrupting the game if the cast file can't be written
+        if let Some(recorder) = &mut integrations.cast_recorder {
+            if recorder.record_frame(scene.frame_buffer(), clock).is_err() {
+                integrations.cast_recorder = None;
+                alert(settings);
+            }
+        }
+        if let Some(server) = &mut integrations.spectator_server {
+            server.broadcast(scene.frame_buffer());
+        }
 
         // Wait till next frame
-        frame_sleep();
+        frame_sleep(clock);
 
         if command == ProgramCommand::Quit {
             break;
         }
     }
+
+    if let Some(recorder) = integrations.cast_recorder {
+        let _ = recorder.finish();
+    }
+
+    return diagnostics;
 }
 
+/// A seed for anything that should differ from run to run
+fn time_seed() -> u64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|since_epoch| since_epoch.as_nanos() as u64).unwrap_or(0);
+}
+
+/// Rings the terminal bell to let the player know something went wrong, unless they asked for quiet
+fn alert(settings: &Settings) {
+    if !settings.quiet {
+        beep();
+    }
+}
@@ -0,0 +1,99 @@
+use std::f64::consts::{FRAC_PI_4, PI};
+
+use super::curses_util::draw_2d::{draw_line, Coordinate};
+use super::curses_util::frame_buffer::{CellStyle, FrameBuffer};
+use super::world::camera::Camera;
+use super::world::pillar::Wall;
+use super::world::util::normalize_range;
+use super::world::world_entity::WorldEntity;
+
+/// Converts world positions into screen positions so that a set of world bounds fills the screen
+struct MapProjection {
+    min_x: f64,
+    max_y: f64,
+    units_per_col: f64,
+    cell_aspect: f64, // A row covers this many times the distance of a column, to keep the map in proportion
+}
+
+impl MapProjection {
+    fn fitting(min_x: f64, max_x: f64, min_y: f64, max_y: f64, rows: i32, cols: i32, cell_aspect: f64) -> MapProjection {
+        let width_units_per_col = (max_x - min_x) / (cols - 1).max(1) as f64;
+        let height_units_per_col = (max_y - min_y) / ((rows - 1).max(1) as f64 * cell_aspect);
+        let units_per_col = width_units_per_col.max(height_units_per_col).max(f64::EPSILON);
+
+        MapProjection { min_x, max_y, units_per_col, cell_aspect }
+    }
+
+    fn to_screen(&self, entity: &impl WorldEntity) -> Coordinate {
+        Coordinate {
+            // World y points up while screen rows count down
+            row: ((self.max_y - entity.y_pos()) / (self.units_per_col * self.cell_aspect)).round() as i32,
+            col: ((entity.x_pos() - self.min_x) / self.units_per_col).round() as i32,
+        }
+    }
+}
+
+/// Draws the walls from above, scaled to fill the buffer, with an arrow for the camera showing
+/// which way it faces. The cell aspect is the height of a terminal cell over its width.
+pub fn draw_top_down_map(buffer: &mut FrameBuffer, camera: &Camera, walls: &[Wall], wall_char: char, cell_aspect: f64) {
+    let mut xs = vec![camera.x_pos()];
+    let mut ys = vec![camera.y_pos()];
+    for wall in walls {
+        xs.extend([wall.pillar1().x_pos(), wall.pillar2().x_pos()]);
+        ys.extend([wall.pillar1().y_pos(), wall.pillar2().y_pos()]);
+    }
+    let min = |values: &[f64]| values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = |values: &[f64]| values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let projection = MapProjection::fitting(min(&xs), max(&xs), min(&ys), max(&ys), buffer.rows(), buffer.cols(), cell_aspect);
+
+    buffer.set_style(CellStyle::Plain);
+    for wall in walls {
+        let from = projection.to_screen(wall.pillar1());
+        let to = projection.to_screen(wall.pillar2());
+        draw_line(buffer, from, to, wall_char);
+        // draw_line stops short of the far end of vertical lines
+        buffer.put_char(to.row, to.col, wall_char);
+    }
+
+    let camera_coords = projection.to_screen(camera);
+    buffer.set_style(CellStyle::Bold);
+    buffer.put_char(camera_coords.row, camera_coords.col, facing_arrow(camera.facing_direction()));
+}
+
+/// The arrow character closest to the given facing direction
+fn facing_arrow(facing_direction: f64) -> char {
+    let quadrant = (normalize_range(facing_direction + FRAC_PI_4, 0.0..2.0 * PI) / (PI / 2.0)) as i32;
+
+    match quadrant {
+        0 => '>',
+        1 => '^',
+        2 => '<',
+        _ => 'v',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{facing_arrow, MapProjection};
+    use crate::world::pillar::Pillar;
+
+    #[test]
+    fn arrows_follow_facing_direction() {
+        assert_eq!('>', facing_arrow(0.1));
+        assert_eq!('^', facing_arrow(PI / 2.0));
+        assert_eq!('<', facing_arrow(PI));
+        assert_eq!('v', facing_arrow(-PI / 2.0));
+    }
+
+    #[test]
+    fn squarer_cells_get_more_rows_per_unit() {
+        let corner = Pillar::at(4.0, 0.0);
+        let usual = MapProjection::fitting(0.0, 4.0, 0.0, 4.0, 100, 9, 2.0);
+        let square = MapProjection::fitting(0.0, 4.0, 0.0, 4.0, 100, 9, 1.0);
+
+        assert_eq!(4, usual.to_screen(&corner).row);
+        assert_eq!(8, square.to_screen(&corner).row);
+    }
+}
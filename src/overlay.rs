@@ -0,0 +1,194 @@
+use std::cmp::max;
+
+use super::curses_util::frame_buffer::{CellStyle, FrameBuffer};
+use super::diagnostics::RunDiagnostics;
+use super::input::{Action, Binding};
+use super::render::{DetailLevel, RenderStats};
+use super::world::camera::Camera;
+use super::world::world_entity::WorldEntity;
+
+/// The widest a histogram bar on the frame time screen gets, in characters
+const HISTOGRAM_BAR_WIDTH: usize = 30;
+
+const HELP_TIPS: [&str; 2] = [
+    "Walls past the horizon aren't drawn, so keep exploring.",
+    "Detail drops automatically if your terminal can't keep up.",
+];
+
+/// Dims the frame and draws a box in the middle of it listing the current key bindings and a few
+/// gameplay tips
+pub fn draw_help_overlay(buffer: &mut FrameBuffer, bindings: &[Binding]) {
+    let mut lines = vec![String::from("Controls"), String::new()];
+    // Actions sharing a description, like the quick save slots, share a line with their keys in slot order
+    let mut descriptions: Vec<&str> = Action::ALL.iter().map(|action| action.description()).collect();
+    descriptions.dedup();
+    for description in descriptions {
+        let keys: Vec<String> = Action::ALL.iter()
+            .filter(|action| action.description() == description)
+            .flat_map(|action| bindings.iter().filter(move |binding| binding.action == *action))
+            .map(|binding| binding.key.to_string())
+            .collect();
+        if !keys.is_empty() {
+            lines.push(format!("{:<20}{}", description, keys.join(", ")));
+        }
+    }
+
+    // Tips are the first thing to go when the box wouldn't fit on screen
+    let tips_rows = HELP_TIPS.len() as i32 + 3;
+    if lines.len() as i32 + tips_rows + 2 <= buffer.rows() {
+        lines.push(String::new());
+        lines.push(String::from("Tips"));
+        lines.push(String::new());
+        lines.extend(HELP_TIPS.iter().map(|tip| tip.to_string()));
+    }
+
+    buffer.restyle_drawn(CellStyle::Dim);
+    draw_text_box(buffer, &lines);
+}
+
+/// Lists the camera's position and how the last frame was rendered in the top left corner
+pub fn draw_debug_overlay(buffer: &mut FrameBuffer, camera: &Camera, stats: &RenderStats, detail: DetailLevel) {
+    let lines = [
+        format!("pos    {:.2}, {:.2}", camera.x_pos(), camera.y_pos()),
+        format!("facing {:.1} deg", camera.facing_direction().to_degrees()),
+        format!("walls  {} drawn / {} considered", stats.walls_drawn, stats.walls_considered),
+        format!("frame  {:.1} ms", stats.last_frame_time.as_secs_f64() * 1000.0),
+        format!("detail {:?}", detail),
+    ];
+
+    buffer.set_style(CellStyle::Reverse);
+    for (line_idx, line) in lines.iter().enumerate() {
+        buffer.put_str(line_idx as i32, 0, line);
+    }
+}
+
+/// Dims the frame and draws a box in the middle of it with frame time percentiles, a histogram of
+/// frame times, and the slowest frames so far
+pub fn draw_diagnostics_overlay(buffer: &mut FrameBuffer, diagnostics: &RunDiagnostics) {
+    let histogram = diagnostics.frame_time_histogram();
+    let mut lines = vec![
+        format!("Frame times over {} frames", histogram.frames()),
+        String::new(),
+        format!("p50 {:.0} ms  p95 {:.0} ms  p99 {:.0} ms  max {:.1} ms", histogram.percentile_ms(0.50), histogram.percentile_ms(0.95), histogram.percentile_ms(0.99), histogram.max_ms()),
+        String::new(),
+    ];
+
+    let bins = histogram.bins();
+    let most_frames = bins.iter().map(|bin| bin.frames).max().unwrap_or(0).max(1);
+    let mut previous_end = None;
+    for bin in &bins {
+        let range = match (previous_end, bin.up_to_ms) {
+            (None, Some(bin_end)) => format!("<= {} ms", bin_end),
+            (Some(previous_end), Some(bin_end)) => format!("{}-{} ms", previous_end + 1, bin_end),
+            (Some(previous_end), None) => format!("> {} ms", previous_end),
+            (None, None) => String::from("all"),
+        };
+        let bar = "#".repeat((bin.frames * HISTOGRAM_BAR_WIDTH).div_ceil(most_frames));
+        lines.push(format!("{:>10} {:<width$} {}", range, bar, bin.frames, width = HISTOGRAM_BAR_WIDTH));
+        previous_end = bin.up_to_ms.or(previous_end);
+    }
+
+    // Stalls are the first thing to go when the box wouldn't fit on screen
+    let stalls = diagnostics.worst_stalls();
+    if !stalls.is_empty() && (lines.len() + stalls.len() + 4) as i32 <= buffer.rows() {
+        lines.push(String::new());
+        lines.push(String::from("Worst stalls"));
+        lines.extend(stalls.iter().map(|stall| format!("frame {:<8} {:.1} ms", stall.frame, stall.ms)));
+    }
+
+    buffer.restyle_drawn(CellStyle::Dim);
+    draw_text_box(buffer, &lines);
+}
+
+/// Replaces the frame with a request for a terminal of at least the given size
+pub fn draw_enlarge_message(buffer: &mut FrameBuffer, min_rows: i32, min_cols: i32) {
+    buffer.clear();
+    buffer.set_style(CellStyle::Plain);
+    buffer.put_str(0, 0, "Please enlarge the terminal");
+    buffer.put_str(1, 0, &format!("to at least {} columns x {} rows", min_cols, min_rows));
+}
+
+/// Marks the top right corner of the screen while a macro is being recorded
+pub fn draw_recording_indicator(buffer: &mut FrameBuffer) {
+    let label = " REC ";
+
+    buffer.set_style(CellStyle::Reverse);
+    buffer.put_str(0, max(buffer.cols() - label.len() as i32, 0), label);
+}
+
+/// Draws a bordered box containing the given lines in the centre of the buffer
+fn draw_text_box(buffer: &mut FrameBuffer, lines: &[String]) {
+    let text_width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as i32;
+    let box_width = text_width + 4;
+    let box_height = lines.len() as i32 + 2;
+    let top = max((buffer.rows() - box_height) / 2, 0);
+    let left = max((buffer.cols() - box_width) / 2, 0);
+    let horizontal_border = format!("+{}+", "-".repeat((box_width - 2) as usize));
+
+    buffer.set_style(CellStyle::Plain);
+    buffer.put_str(top, left, &horizontal_border);
+    for (line_idx, line) in lines.iter().enumerate() {
+        buffer.put_str(top + 1 + line_idx as i32, left, &format!("| {:<width$} |", line, width = text_width as usize));
+    }
+    buffer.put_str(top + box_height - 1, left, &horizontal_border);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use device_query::Keycode;
+
+    use super::{draw_diagnostics_overlay, draw_help_overlay};
+    use crate::curses_util::frame_buffer::FrameBuffer;
+    use crate::diagnostics::RunDiagnostics;
+    use crate::input::{Action, Binding};
+    use crate::render::RenderStats;
+
+    #[test]
+    fn lists_bound_keys() {
+        let mut buffer = FrameBuffer::with_dimensions(20, 80);
+        let bindings = vec![Binding { key: Keycode::K, action: Action::MoveForward }, Binding { key: Keycode::J, action: Action::MoveForward }];
+        draw_help_overlay(&mut buffer, &bindings);
+
+        let text = buffer.to_text();
+        assert!(text.contains("Move forward        K, J"));
+        assert!(!text.contains("Turn left"));
+    }
+
+    #[test]
+    fn lists_save_slots_on_one_line() {
+        let mut buffer = FrameBuffer::with_dimensions(20, 80);
+        let bindings = vec![Binding { key: Keycode::F7, action: Action::SaveState(1) }, Binding { key: Keycode::F6, action: Action::SaveState(0) }];
+        draw_help_overlay(&mut buffer, &bindings);
+
+        assert!(buffer.to_text().contains("Quick save position F6, F7"));
+    }
+
+    #[test]
+    fn drops_tips_when_short_on_rows() {
+        let mut buffer = FrameBuffer::with_dimensions(8, 80);
+        let bindings = vec![Binding { key: Keycode::K, action: Action::MoveForward }];
+        draw_help_overlay(&mut buffer, &bindings);
+
+        let text = buffer.to_text();
+        assert!(text.contains("Move forward"));
+        assert!(!text.contains("Tips"));
+    }
+
+    #[test]
+    fn shows_frame_time_percentiles_and_stalls() {
+        let mut diagnostics = RunDiagnostics::new(0, 0);
+        for frame_ms in [10, 12, 300] {
+            let stats = RenderStats { last_frame_time: Duration::from_millis(frame_ms), ..RenderStats::default() };
+            diagnostics.record_frame(&stats, Vec::new());
+        }
+        let mut buffer = FrameBuffer::with_dimensions(24, 80);
+        draw_diagnostics_overlay(&mut buffer, &diagnostics);
+
+        let text = buffer.to_text();
+        assert!(text.contains("p50 12 ms"));
+        assert!(text.contains("> 250 ms"));
+        assert!(text.contains("frame 2        300.0 ms"));
+    }
+}
@@ -0,0 +1,115 @@
+use std::f64::consts::PI;
+
+use super::render::RENDER_FPS;
+use super::rng::Rng;
+use super::world::camera::Camera;
+use super::world::world_entity::WorldEntity;
+
+const PARTICLE_COUNT: usize = 40;
+/// How far from the camera particles are scattered. Particles drifting further away are moved
+/// back near the camera.
+const SPAWN_RADIUS: f64 = 6.0;
+/// The fastest a particle drifts across the ground, in world units per second
+const MAX_DRIFT_SPEED: f64 = 0.3;
+/// The fastest a particle rises or falls, in wall heights per second
+const MAX_RISE_SPEED: f64 = 0.05;
+
+/// A mote of dust floating in the world
+pub struct Particle {
+    x_pos: f64,
+    y_pos: f64,
+    height: f64, // From -1.0 at the floor to 1.0 at the top of the walls
+    x_drift: f64,
+    y_drift: f64,
+    rise: f64,
+}
+
+impl WorldEntity for Particle {
+    fn x_pos(&self) -> f64 {
+        self.x_pos
+    }
+    fn y_pos(&self) -> f64 {
+        self.y_pos
+    }
+}
+
+impl Particle {
+    /// How high the particle floats, from -1.0 at the floor to 1.0 at the top of the walls
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+}
+
+/// Dust drifting through the air around the camera to make empty corridors feel less still
+pub struct ParticleField {
+    particles: Vec<Particle>,
+    rng: Rng,
+}
+
+impl ParticleField {
+    /// Creates a field with no particles. The seed picks where particles appear and how they drift.
+    pub fn with_seed(seed: u64) -> ParticleField {
+        ParticleField { particles: Vec::new(), rng: Rng::with_seed(seed) }
+    }
+
+    /// The particles currently floating around the camera
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Drifts every particle along by one frame, scattering new particles around the camera to
+    /// replace any which have drifted too far from it
+    pub fn update(&mut self, camera: &Camera) {
+        for particle in &mut self.particles {
+            particle.x_pos += particle.x_drift / RENDER_FPS;
+            particle.y_pos += particle.y_drift / RENDER_FPS;
+            particle.height += particle.rise / RENDER_FPS;
+            if particle.height.abs() > 1.0 {
+                particle.rise = -particle.rise;
+                particle.height = particle.height.clamp(-1.0, 1.0);
+            }
+        }
+
+        self.particles.retain(|particle| camera.distance_to(particle) <= SPAWN_RADIUS);
+        while self.particles.len() < PARTICLE_COUNT {
+            let particle = self.spawn_near(camera);
+            self.particles.push(particle);
+        }
+    }
+
+    fn spawn_near(&mut self, camera: &Camera) -> Particle {
+        let angle = self.rng.next_unit() * 2.0 * PI;
+        // Square root spreads particles evenly over the circle's area rather than bunching them at the centre
+        let distance = self.rng.next_unit().sqrt() * SPAWN_RADIUS;
+
+        return Particle {
+            x_pos: camera.x_pos() + distance * angle.cos(),
+            y_pos: camera.y_pos() + distance * angle.sin(),
+            height: self.rng.next_signed(1.0),
+            x_drift: self.rng.next_signed(MAX_DRIFT_SPEED),
+            y_drift: self.rng.next_signed(MAX_DRIFT_SPEED),
+            rise: self.rng.next_signed(MAX_RISE_SPEED),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParticleField, PARTICLE_COUNT, SPAWN_RADIUS};
+    use crate::world::camera::Camera;
+    use crate::world::world_entity::WorldEntity;
+
+    #[test]
+    fn keeps_particles_around_camera() {
+        let mut field = ParticleField::with_seed(7);
+        let camera = Camera::new();
+        field.update(&camera);
+
+        let moved_camera = camera.update_cam(20.0, 0.0);
+        field.update(&moved_camera);
+
+        assert_eq!(PARTICLE_COUNT, field.particles().len());
+        assert!(field.particles().iter().all(|particle| moved_camera.distance_to(particle) <= SPAWN_RADIUS));
+        assert!(field.particles().iter().all(|particle| particle.height().abs() <= 1.0));
+    }
+}
@@ -1,24 +1,250 @@
 use std::f64::consts::PI;
-use std::thread::sleep;
+use std::ops::RangeInclusive;
 use std::time::Duration;
 
 use ncurses::*;
 
+use super::clock::Clock;
 use super::curses_util::draw_2d::*;
+use super::curses_util::frame_buffer::{CellStyle, FrameBuffer};
+use super::lighting::flashlight_light_level;
+use super::map_view::draw_top_down_map;
+use super::particles::Particle;
 use super::world::camera::Camera;
+use super::world::decal::Decal;
 use super::world::pillar::{Pillar, Wall};
 use super::world::util::{normalize_range};
 use super::world::world_entity::WorldEntity;
 
 pub const RENDER_FPS: f64 = 30.0;
 
-pub fn frame_sleep() {
-    sleep(Duration::from_millis((1000.0 / RENDER_FPS) as u64));
+/// Number of consecutive frames over or under budget before the detail level changes
+const DETAIL_CHANGE_FRAMES: u32 = 15;
+/// Each time raised detail is dropped again soon after, the run of fast frames needed before the
+/// next raise doubles, up to this many frames
+const MAX_DETAIL_RAISE_FRAMES: u32 = 480;
+/// How long raised detail has to last before the next raise goes back to needing the usual run
+const DETAIL_RAISE_SETTLE_FRAMES: u32 = 150;
+/// The fraction of the usual draw distance still drawn out to when there is no light at all
+const DARKNESS_HORIZON_SCALE: f64 = 0.5;
+/// How much of a pillar's height a door lintel stub reaches out over the opening, as a divisor
+const DOOR_LINTEL_DIVISOR: i32 = 4;
+/// The fewest rows a wall can span at a decal's position for the decal to be readable
+const DECAL_MIN_HEIGHT: i32 = 4;
+/// Particles closer than this are drawn large, and those further than the fade distance are dimmed
+const PARTICLE_NEAR_DISTANCE: f64 = 2.0;
+const PARTICLE_FADE_DISTANCE: f64 = 4.0;
+/// The height of a terminal cell over its width which the projection was tuned for. Cells with
+/// other shapes have walls raised or lowered to keep the same proportions.
+const REFERENCE_CELL_ASPECT: f64 = 2.0;
+/// The cell shapes the projection can keep in proportion. Anything outside pushes walls far off screen.
+pub const CELL_ASPECT_RANGE: RangeInclusive<f64> = 0.25..=8.0;
+
+/// Waits for the length of one frame to pass on the given clock
+pub fn frame_sleep(clock: &impl Clock) {
+    clock.sleep(Duration::from_millis((1000.0 / RENDER_FPS) as u64));
+}
+
+/// The time available to process a single frame
+pub fn frame_budget() -> Duration {
+    Duration::from_secs_f64(1.0 / RENDER_FPS)
+}
+
+/// How much of the scene gets drawn. Each level down trades more visual detail for frame time.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DetailLevel {
+    Full,
+    NoFill,
+    NoFillShortHorizon,
+}
+
+impl DetailLevel {
+    fn lower(self) -> DetailLevel {
+        match self {
+            DetailLevel::Full => DetailLevel::NoFill,
+            DetailLevel::NoFill | DetailLevel::NoFillShortHorizon => DetailLevel::NoFillShortHorizon,
+        }
+    }
+
+    fn higher(self) -> DetailLevel {
+        match self {
+            DetailLevel::Full | DetailLevel::NoFill => DetailLevel::Full,
+            DetailLevel::NoFillShortHorizon => DetailLevel::NoFill,
+        }
+    }
+
+    /// True if wall faces should be filled in at this level
+    pub fn fills_walls(self) -> bool {
+        self == DetailLevel::Full
+    }
+
+    /// The fraction of the camera's horizon distance that walls are drawn out to at this level
+    pub fn horizon_scale(self) -> f64 {
+        match self {
+            DetailLevel::NoFillShortHorizon => 0.5,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Watches frame times and picks a detail level which keeps frames within budget
+pub struct AdaptiveDetail {
+    level: DetailLevel,
+    over_budget_frames: u32,
+    under_budget_frames: u32,
+    /// Fast frames needed before detail is raised, which grows while raises keep failing
+    raise_frames: u32,
+    /// Frames drawn since detail was last raised, until the raise has settled
+    frames_since_raise: Option<u32>,
+}
+
+impl AdaptiveDetail {
+    pub fn new() -> AdaptiveDetail {
+        AdaptiveDetail {
+            level: DetailLevel::Full,
+            over_budget_frames: 0,
+            under_budget_frames: 0,
+            raise_frames: DETAIL_CHANGE_FRAMES,
+            frames_since_raise: None,
+        }
+    }
+
+    /// The detail level the next frame should be drawn at
+    pub fn level(&self) -> DetailLevel {
+        self.level
+    }
+
+    /// Records how long the last frame took, lowering detail after a run of slow frames and
+    /// raising it again after a run of frames well under budget. Raises which don't last make the
+    /// next one wait longer so detail doesn't flip back and forth.
+    pub fn record_frame_time(&mut self, frame_time: Duration) {
+        let budget = frame_budget();
+
+        if let Some(frames) = self.frames_since_raise {
+            if frames >= DETAIL_RAISE_SETTLE_FRAMES {
+                self.raise_frames = DETAIL_CHANGE_FRAMES;
+                self.frames_since_raise = None;
+            } else {
+                self.frames_since_raise = Some(frames + 1);
+            }
+        }
+
+        if frame_time > budget {
+            self.over_budget_frames += 1;
+            self.under_budget_frames = 0;
+        } else if frame_time < budget / 2 {
+            self.under_budget_frames += 1;
+            self.over_budget_frames = 0;
+        } else {
+            self.over_budget_frames = 0;
+            self.under_budget_frames = 0;
+        }
+
+        if self.over_budget_frames >= DETAIL_CHANGE_FRAMES {
+            self.level = self.level.lower();
+            self.over_budget_frames = 0;
+            if self.frames_since_raise.take().is_some() {
+                self.raise_frames = (self.raise_frames * 2).min(MAX_DETAIL_RAISE_FRAMES);
+            }
+        }
+        if self.under_budget_frames >= self.raise_frames {
+            let raised = self.level.higher();
+            if raised != self.level {
+                self.frames_since_raise = Some(0);
+            }
+            self.level = raised;
+            self.under_budget_frames = 0;
+        }
+    }
+}
+
+/// The characters used to draw the scene
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Charset {
+    pub wall_edge: char,
+    pub wall_fill: char,
+    /// Softens sloped edges in cells the edge reaches well into and only just into
+    pub soft_edge_heavy: char,
+    pub soft_edge_light: char,
+    /// Dust motes close to the camera and further away
+    pub particle_near: char,
+    pub particle_far: char,
+}
+
+impl Charset {
+    pub const CLASSIC: Charset = Charset { wall_edge: '#', wall_fill: '.', soft_edge_heavy: ':', soft_edge_light: '\'', particle_near: '*', particle_far: '.' };
+    pub const BLOCKS: Charset = Charset { wall_edge: '█', wall_fill: '▒', soft_edge_heavy: '▓', soft_edge_light: '░', particle_near: '▪', particle_far: '·' };
+    pub const DOTS: Charset = Charset { wall_edge: 'o', wall_fill: '·', soft_edge_heavy: ':', soft_edge_light: '.', particle_near: '•', particle_far: '·' };
+
+    /// Looks up one of the built-in charsets by name
+    pub fn named(name: &str) -> Option<Charset> {
+        match name {
+            "classic" => Some(Charset::CLASSIC),
+            "blocks" => Some(Charset::BLOCKS),
+            "dots" => Some(Charset::DOTS),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Charset {
+    fn default() -> Charset {
+        Charset::CLASSIC
+    }
+}
+
+/// Player-chosen options controlling how the scene looks
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RenderStyle {
+    /// Draw edges in bold and fill walls with solid reverse-video blocks
+    pub high_contrast: bool,
+    pub charset: Charset,
+    /// Frame the wall ends marked as doorways so gaps read as openings rather than missing geometry
+    pub door_frames: bool,
+    /// Soften the steps in sloped wall edges with lighter characters
+    pub soft_edges: bool,
+}
+
+/// Figures about the most recently drawn frame, for diagnosing rendering problems
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RenderStats {
+    pub walls_considered: usize,
+    pub walls_drawn: usize,
+    pub last_frame_time: Duration,
+}
+
+/// How brightly a wall is drawn, from the light falling on it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum WallShade {
+    Lit,
+    Dim,
+    Dark,
+}
+
+impl WallShade {
+    fn from_light_level(light_level: f64) -> WallShade {
+        if light_level >= 0.66 {
+            WallShade::Lit
+        } else if light_level >= 0.33 {
+            WallShade::Dim
+        } else {
+            WallShade::Dark
+        }
+    }
 }
 
 pub struct Scene {
     screen_rows: i32,
     screen_cols: i32,
+    frame_buffer: FrameBuffer,
+    detail: AdaptiveDetail,
+    style: RenderStyle,
+    stats: RenderStats,
+    render_errors: Vec<TriangleFillErr>,
+    light_level: f64, // From 0.0 for pitch black to 1.0 for full light
+    flashlight_on: bool,
+    draw_distance_scale: f64,
+    cell_aspect: f64, // Height of a terminal cell over its width
 }
 
 #[derive(Copy, Clone)]
@@ -30,14 +256,122 @@ struct PillarCoords {
 impl Scene {
     /// Creates a new scene with the given screen dimensions
     pub fn with_dimensions(screen_rows: i32, screen_cols: i32) -> Scene {
-        Scene { screen_rows, screen_cols }
+        Scene {
+            screen_rows,
+            screen_cols,
+            frame_buffer: FrameBuffer::with_dimensions(screen_rows, screen_cols),
+            detail: AdaptiveDetail::new(),
+            style: RenderStyle::default(),
+            stats: RenderStats::default(),
+            render_errors: Vec::new(),
+            light_level: 1.0,
+            flashlight_on: false,
+            draw_distance_scale: 1.0,
+            cell_aspect: REFERENCE_CELL_ASPECT,
+        }
+    }
+
+    /// Changes the screen dimensions frames are drawn at, starting over with a blank frame
+    pub fn resize(&mut self, screen_rows: i32, screen_cols: i32) {
+        self.screen_rows = screen_rows;
+        self.screen_cols = screen_cols;
+        self.frame_buffer = FrameBuffer::with_dimensions(screen_rows, screen_cols);
+    }
+
+    /// Changes how the scene is drawn from the next frame onward
+    pub fn set_style(&mut self, style: RenderStyle) {
+        self.style = style;
     }
 
-    pub fn render_frame(&self, camera: &Camera, walls: &Vec<Wall>) {
-        clear();
+    /// Changes how much light falls on the world, from 0.0 for pitch black to 1.0 for full light
+    pub fn set_light_level(&mut self, light_level: f64) {
+        self.light_level = light_level.clamp(0.0, 1.0);
+    }
+
+    /// Turns the flashlight lighting up walls straight ahead of the camera on or off
+    pub fn set_flashlight(&mut self, flashlight_on: bool) {
+        self.flashlight_on = flashlight_on;
+    }
+
+    /// Scales how far from the camera walls are drawn, on top of any cuts made for detail or light
+    pub fn set_draw_distance_scale(&mut self, draw_distance_scale: f64) {
+        self.draw_distance_scale = draw_distance_scale;
+    }
 
-        for wall in walls {
-            if camera.can_see_viewable(wall) {
+    /// Tells the scene the height of the terminal's cells over their width, so walls keep their
+    /// proportions in fonts with taller or squatter cells
+    pub fn set_cell_aspect(&mut self, cell_aspect: f64) {
+        self.cell_aspect = cell_aspect;
+    }
+
+    /// Records how long the last frame took so the scene can adjust its level of detail
+    pub fn record_frame_time(&mut self, frame_time: Duration) {
+        self.detail.record_frame_time(frame_time);
+        self.stats.last_frame_time = frame_time;
+    }
+
+    /// Figures about the most recently drawn frame
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Hands over the errors hit while drawing since this was last called
+    pub fn take_render_errors(&mut self) -> Vec<TriangleFillErr> {
+        std::mem::take(&mut self.render_errors)
+    }
+
+    /// The level of detail frames are currently drawn at
+    pub fn detail_level(&self) -> DetailLevel {
+        self.detail.level()
+    }
+
+    /// Draws a top-down map of the walls and camera into the scene's frame buffer instead of the
+    /// first person view
+    pub fn draw_map(&mut self, camera: &Camera, walls: &[Wall]) {
+        self.frame_buffer.clear();
+        draw_top_down_map(&mut self.frame_buffer, camera, walls, self.style.charset.wall_edge, self.cell_aspect);
+    }
+
+    /// Sends the drawn frame, along with anything drawn over it, to the terminal
+    pub fn present(&mut self) {
+        self.frame_buffer.flush();
+        refresh();
+    }
+
+    /// Draws the walls visible to the camera into the scene's frame buffer without presenting it
+    pub fn draw_frame(&mut self, camera: &Camera, walls: &[Wall]) {
+        self.frame_buffer.clear();
+
+        let detail = self.detail.level();
+        let edge_char = self.style.charset.wall_edge;
+        let (edge_style, fill_style, fill_char) = if self.style.high_contrast {
+            (CellStyle::Bold, CellStyle::Reverse, ' ')
+        } else {
+            (CellStyle::Plain, CellStyle::Plain, self.style.charset.wall_fill)
+        };
+        let light_horizon_scale = DARKNESS_HORIZON_SCALE + (1.0 - DARKNESS_HORIZON_SCALE) * self.light_level;
+        let draw_distance = camera.horizon_distance() * self.draw_distance_scale * detail.horizon_scale() * light_horizon_scale;
+
+        self.stats.walls_considered = walls.len();
+        self.stats.walls_drawn = 0;
+
+        // Draw from the furthest wall to the nearest so nearer walls paint over the ones behind them.
+        // Distance is taken to the closest point on each wall since a single pillar can be far away
+        // on a long wall seen at an angle.
+        let mut sorted_walls: Vec<&Wall> = walls.iter().collect();
+        sorted_walls.sort_by(|wall1, wall2| wall2.distance_from(camera).total_cmp(&wall1.distance_from(camera)));
+
+        for wall in sorted_walls {
+            if wall.distance_from(camera) < draw_distance && camera.can_see_viewable(wall) {
+                self.stats.walls_drawn += 1;
+
+                // High contrast walls keep their full styles whatever the light
+                let shade = if self.style.high_contrast { WallShade::Lit } else { WallShade::from_light_level(self.wall_light_level(camera, wall)) };
+                let (edge_style, fill_style) = match shade {
+                    WallShade::Lit => (edge_style, fill_style),
+                    WallShade::Dim => (edge_style, CellStyle::Dim),
+                    WallShade::Dark => (CellStyle::Dim, CellStyle::Dim),
+                };
                 let pillar1_screen_coords = self.calculate_pillar_coords(camera, wall.pillar1());
                 let pillar2_screen_coords = self.calculate_pillar_coords(camera, wall.pillar2());
 
@@ -48,35 +382,159 @@ impl Scene {
                 };
 
                 // Only fill if there is a space of at least one column between the pillars
-                if right_pillar_coords.line_top.col - left_pillar_coords.line_top.col > 2 {
+                if detail.fills_walls() && shade != WallShade::Dark && right_pillar_coords.line_top.col - left_pillar_coords.line_top.col > 2 {
                     let top_left_fillshift = left_pillar_coords.line_top.coord_shift(1, 1);
                     let bottom_left_fillshift = left_pillar_coords.line_bottom.coord_shift(-1, 1);
                     let top_right_fillshift = right_pillar_coords.line_top.coord_shift(1, -1);
                     let bottom_right_fillshift = right_pillar_coords.line_bottom.coord_shift(-1, -1);
 
-                    // TODO do something with the results here
-                    fill_triangle(top_left_fillshift, bottom_left_fillshift, top_right_fillshift, '.');
-                    fill_triangle(bottom_left_fillshift, top_right_fillshift, bottom_right_fillshift, '.');
+                    // A failed fill only leaves part of a wall blank, so keep going and report it later
+                    self.frame_buffer.set_style(fill_style);
+                    if let Err(err) = fill_triangle(&mut self.frame_buffer, top_left_fillshift, bottom_left_fillshift, top_right_fillshift, fill_char) {
+                        self.render_errors.push(err);
+                    }
+                    if let Err(err) = fill_triangle(&mut self.frame_buffer, bottom_left_fillshift, top_right_fillshift, bottom_right_fillshift, fill_char) {
+                        self.render_errors.push(err);
+                    }
+                }
+
+                if let Some(decal) = wall.decal() {
+                    if shade != WallShade::Dark {
+                        self.draw_decal(camera, wall, decal, left_pillar_coords.line_top.col, right_pillar_coords.line_top.col);
+                    }
+                }
+
+                self.frame_buffer.set_style(edge_style);
+                draw_line(&mut self.frame_buffer, pillar1_screen_coords.line_top, pillar1_screen_coords.line_bottom, edge_char);
+                draw_line(&mut self.frame_buffer, pillar2_screen_coords.line_top, pillar2_screen_coords.line_bottom, edge_char);
+                draw_line(&mut self.frame_buffer, pillar1_screen_coords.line_top, pillar2_screen_coords.line_top, edge_char);
+                draw_line(&mut self.frame_buffer, pillar1_screen_coords.line_bottom, pillar2_screen_coords.line_bottom, edge_char);
+
+                if self.style.soft_edges {
+                    self.frame_buffer.set_style(CellStyle::Dim);
+                    soften_line(&mut self.frame_buffer, pillar1_screen_coords.line_top, pillar2_screen_coords.line_top, self.style.charset.soft_edge_heavy, self.style.charset.soft_edge_light);
+                    soften_line(&mut self.frame_buffer, pillar1_screen_coords.line_bottom, pillar2_screen_coords.line_bottom, self.style.charset.soft_edge_heavy, self.style.charset.soft_edge_light);
+                    self.frame_buffer.set_style(edge_style);
+                }
+
+                let (doorway_at_pillar1, doorway_at_pillar2) = wall.doorway_ends();
+                if self.style.door_frames && doorway_at_pillar1 {
+                    self.draw_door_frame(pillar1_screen_coords, pillar2_screen_coords, edge_char);
                 }
+                if self.style.door_frames && doorway_at_pillar2 {
+                    self.draw_door_frame(pillar2_screen_coords, pillar1_screen_coords, edge_char);
+                }
+            }
+        }
+    }
+
+    /// Draws particles into the empty space of the drawn frame, fading them with distance. They're
+    /// never drawn over walls so they can't get in the way of reading the scene.
+    pub fn draw_particles(&mut self, camera: &Camera, particles: &[Particle]) {
+        for particle in particles {
+            if !camera.can_see(particle) {
+                continue;
+            }
+
+            let distance = camera.distance_to(particle);
+            let charset = self.style.charset;
+            let (particle_char, particle_style) = if distance < PARTICLE_NEAR_DISTANCE {
+                (charset.particle_near, CellStyle::Plain)
+            } else if distance < PARTICLE_FADE_DISTANCE {
+                (charset.particle_far, CellStyle::Plain)
+            } else {
+                (charset.particle_far, CellStyle::Dim)
+            };
+
+            // Place the particle between where the floor and the top of a wall would be at its distance
+            let coords = self.calculate_pillar_coords(camera, particle);
+            let half_height = (coords.line_bottom.row - coords.line_top.row) as f64 / 2.0;
+            let row = (coords.line_top.row as f64 + half_height * (1.0 - particle.height())) as i32;
+            let col = coords.line_top.col;
 
-                draw_line(pillar1_screen_coords.line_top, pillar1_screen_coords.line_bottom, '#');
-                draw_line(pillar2_screen_coords.line_top, pillar2_screen_coords.line_bottom, '#');
-                draw_line(pillar1_screen_coords.line_top, pillar2_screen_coords.line_top, '#');
-                draw_line(pillar1_screen_coords.line_bottom, pillar2_screen_coords.line_bottom, '#');
+            if self.frame_buffer.is_blank(row, col) {
+                self.frame_buffer.set_style(particle_style);
+                self.frame_buffer.put_char(row, col, particle_char);
             }
         }
+    }
 
-        refresh();
+    /// The buffer holding the most recently drawn frame
+    pub fn frame_buffer(&mut self) -> &mut FrameBuffer {
+        &mut self.frame_buffer
     }
 
+    /// The light falling on a wall, taking the flashlight's beam into account
+    fn wall_light_level(&self, camera: &Camera, wall: &Wall) -> f64 {
+        if !self.flashlight_on {
+            return self.light_level;
+        }
+
+        let (closest_x, closest_y) = wall.closest_point_to(camera);
+        let angle_from_center = normalize_range(camera.view_angle_from_center(&Pillar::at(closest_x, closest_y)), -PI..PI);
 
-    fn calculate_pillar_coords(&self, camera: &Camera, pillar: &Pillar) -> PillarCoords {
+        return flashlight_light_level(self.light_level, angle_from_center);
+    }
+
+    /// Draws a decal as a plate on the wall's surface, scaled with the wall's height where it sits
+    /// and kept between the wall's left and right columns. Decals on walls too far away to read
+    /// are left off.
+    fn draw_decal(&mut self, camera: &Camera, wall: &Wall, decal: &Decal, left_col: i32, right_col: i32) {
+        let (decal_x, decal_y) = wall.point_along(decal.along_wall());
+        let decal_coords = self.calculate_pillar_coords(camera, &Pillar::at(decal_x, decal_y));
+        let wall_height = decal_coords.line_bottom.row - decal_coords.line_top.row;
+        if wall_height < DECAL_MIN_HEIGHT {
+            return;
+        }
+
+        let text_width = decal.text().chars().count() as i32;
+        // An odd number of rows keeps the text on the middle one
+        let plate_rows = (wall_height / 4).max(1) | 1;
+        let plate_cols = text_width + 2 * plate_rows;
+        let plate_top = (decal_coords.line_top.row + decal_coords.line_bottom.row) / 2 - plate_rows / 2;
+        let plate_left = decal_coords.line_top.col - plate_cols / 2;
+        let text_row = plate_top + plate_rows / 2;
+        let text_left = plate_left + plate_rows;
+
+        self.frame_buffer.set_style(CellStyle::Reverse);
+        for row in plate_top..plate_top + plate_rows {
+            for col in plate_left.max(left_col + 1)..(plate_left + plate_cols).min(right_col) {
+                let text_idx = col - text_left;
+                let plate_char = if row == text_row && (0..text_width).contains(&text_idx) {
+                    decal.text().chars().nth(text_idx as usize).unwrap_or(' ')
+                } else {
+                    ' '
+                };
+                self.frame_buffer.put_char(row, col, plate_char);
+            }
+        }
+    }
+
+    /// Draws a jamb beside the pillar at the open end of a wall, plus a short lintel stub reaching
+    /// out over the opening, on the side facing away from the wall's other pillar
+    fn draw_door_frame(&mut self, open_end: PillarCoords, other_end: PillarCoords, edge_char: char) {
+        let outward = (open_end.line_top.col - other_end.line_top.col).signum();
+        if outward == 0 {
+            return;
+        }
+
+        let lintel_length = ((open_end.line_bottom.row - open_end.line_top.row) / DOOR_LINTEL_DIVISOR).max(1);
+
+        draw_line(&mut self.frame_buffer, open_end.line_top.coord_shift(0, outward), open_end.line_bottom.coord_shift(0, outward), edge_char);
+        draw_line(&mut self.frame_buffer, open_end.line_top, open_end.line_top.coord_shift(0, outward * lintel_length), edge_char);
+    }
+
+    fn calculate_pillar_coords(&self, camera: &Camera, pillar: &impl WorldEntity) -> PillarCoords {
         let pillar_dist = camera.distance_to(pillar);
         let pillar_ang = normalize_range(camera.view_angle_from_center(pillar), -PI..PI);
         let half_screen_rows = self.screen_rows / 2;
         let half_screen_cols = self.screen_cols / 2;
 
-        let horizon_rise = half_screen_rows as f64 * (1.0 - (pillar_dist - camera.fill_screen_distance()) / (camera.horizon_distance() - camera.fill_screen_distance()));
+        // The field of view always spans the screen's columns, so only the height needs to change
+        // to keep walls in proportion: taller cells need fewer rows to reach the same height
+        let aspect_correction = REFERENCE_CELL_ASPECT / self.cell_aspect;
+
+        let horizon_rise = aspect_correction * half_screen_rows as f64 * (1.0 - (pillar_dist - camera.fill_screen_distance()) / (camera.horizon_distance() - camera.fill_screen_distance()));
         let pillar_top = (half_screen_rows as f64 - horizon_rise) as i32;
         let pillar_bottom = (half_screen_rows as f64 + horizon_rise) as i32;
         let pillar_column = ((pillar_ang / camera.fov_angle()) * self.screen_cols as f64) as i32 + half_screen_cols;
@@ -88,3 +546,107 @@ impl Scene {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{AdaptiveDetail, DetailLevel, PillarCoords, RenderStyle, Scene, DETAIL_CHANGE_FRAMES, DOOR_LINTEL_DIVISOR};
+    use crate::world::camera::Camera;
+    use crate::world::decal::Decal;
+    use crate::world::doorway::mark_doorways;
+    use crate::world::pillar::{Pillar, Wall};
+
+    #[test]
+    fn lowers_detail_after_slow_frames() {
+        let mut detail = AdaptiveDetail::new();
+        for _ in 0..DETAIL_CHANGE_FRAMES {
+            detail.record_frame_time(Duration::from_secs(1));
+        }
+
+        assert_eq!(DetailLevel::NoFill, detail.level());
+    }
+
+    #[test]
+    fn restores_detail_after_fast_frames() {
+        let mut detail = AdaptiveDetail::new();
+        for _ in 0..DETAIL_CHANGE_FRAMES {
+            detail.record_frame_time(Duration::from_secs(1));
+        }
+        for _ in 0..DETAIL_CHANGE_FRAMES {
+            detail.record_frame_time(Duration::from_millis(1));
+        }
+
+        assert_eq!(DetailLevel::Full, detail.level());
+    }
+
+    #[test]
+    fn backs_off_raising_detail_that_keeps_failing() {
+        let mut detail = AdaptiveDetail::new();
+        let mut raised_at = Vec::new();
+        for frame in 0..1000 {
+            let frame_time = if detail.level() == DetailLevel::Full { Duration::from_secs(1) } else { Duration::from_millis(1) };
+            let level_before = detail.level();
+            detail.record_frame_time(frame_time);
+            if detail.level() == DetailLevel::Full && level_before != DetailLevel::Full {
+                raised_at.push(frame);
+            }
+        }
+
+        // Without backing off detail would be raised every other run of frames, over 30 times
+        assert!(raised_at.len() < 8);
+        let gaps: Vec<_> = raised_at.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(gaps.windows(2).all(|pair| pair[1] > pair[0]));
+    }
+
+    #[test]
+    fn draws_decal_on_wall_ahead() {
+        let pillars = [Pillar::at(4.0, 3.0), Pillar::at(4.0, -3.0)];
+        let mut wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+        wall.set_decal(Decal::at("EXIT", 0.5));
+        let mut scene = Scene::with_dimensions(24, 80);
+        scene.draw_frame(&Camera::new(), &[wall]);
+
+        assert!(scene.frame_buffer().to_text().contains("EXIT"));
+    }
+
+    #[test]
+    fn taller_cells_shorten_walls() {
+        let scene = Scene::with_dimensions(24, 80);
+        let mut tall_cell_scene = Scene::with_dimensions(24, 80);
+        tall_cell_scene.set_cell_aspect(4.0);
+        let pillar = Pillar::at(4.0, 0.0);
+
+        let height = |coords: PillarCoords| coords.line_bottom.row - coords.line_top.row;
+        let usual_height = height(scene.calculate_pillar_coords(&Camera::new(), &pillar));
+        let tall_cell_height = height(tall_cell_scene.calculate_pillar_coords(&Camera::new(), &pillar));
+        assert!(tall_cell_height <= usual_height / 2 + 1);
+        assert!(tall_cell_height < usual_height);
+    }
+
+    #[test]
+    fn frames_doorway_between_walls() {
+        let pillars = [Pillar::at(4.0, 3.0), Pillar::at(4.0, 1.0), Pillar::at(4.0, -1.0), Pillar::at(4.0, -3.0)];
+        let mut walls = [Wall::from_pillars(&pillars[0], &pillars[1]), Wall::from_pillars(&pillars[2], &pillars[3])];
+        mark_doorways(&mut walls);
+
+        let mut plain_scene = Scene::with_dimensions(24, 80);
+        plain_scene.draw_frame(&Camera::new(), &walls);
+        let mut framed_scene = Scene::with_dimensions(24, 80);
+        framed_scene.set_style(RenderStyle { door_frames: true, ..RenderStyle::default() });
+        framed_scene.draw_frame(&Camera::new(), &walls);
+
+        // The end of the left wall at the doorway, with the gap lying to its right
+        let end = framed_scene.calculate_pillar_coords(&Camera::new(), &pillars[1]);
+        let lintel_length = (end.line_bottom.row - end.line_top.row) / DOOR_LINTEL_DIVISOR;
+        let plain_text = plain_scene.frame_buffer().to_text();
+        let framed_text = framed_scene.frame_buffer().to_text();
+        let cell = |text: &str, row: i32, col: i32| text.lines().nth(row as usize).unwrap().chars().nth(col as usize).unwrap_or(' ');
+        let jamb = (12, end.line_top.col + 1);
+        let lintel_tip = (end.line_top.row, end.line_top.col + lintel_length);
+
+        assert_eq!(' ', cell(&plain_text, jamb.0, jamb.1));
+        assert_eq!(' ', cell(&plain_text, lintel_tip.0, lintel_tip.1));
+        assert_eq!('#', cell(&framed_text, jamb.0, jamb.1));
+        assert_eq!('#', cell(&framed_text, lintel_tip.0, lintel_tip.1));
+    }
+}
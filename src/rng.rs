@@ -0,0 +1,43 @@
+/// Generates a repeatable series of pseudo-random numbers from a seed (xorshift64*)
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn with_seed(seed: u64) -> Rng {
+        // The generator gets stuck on a state of zero
+        Rng { state: seed.max(1) }
+    }
+
+    /// A number between 0.0 and 1.0
+    pub fn next_unit(&mut self) -> f64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        let value = self.state.wrapping_mul(0x2545_f491_4f6c_dd1d);
+
+        return (value >> 11) as f64 / (1u64 << 53) as f64;
+    }
+
+    /// A number between -max and max
+    pub fn next_signed(&mut self, max: f64) -> f64 {
+        return (self.next_unit() * 2.0 - 1.0) * max;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn repeats_for_the_same_seed() {
+        let mut rng_a = Rng::with_seed(42);
+        let mut rng_b = Rng::with_seed(42);
+
+        for _ in 0..100 {
+            let value = rng_a.next_unit();
+            assert_eq!(value, rng_b.next_unit());
+            assert!((0.0..1.0).contains(&value));
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::curses_util::frame_buffer::FrameBuffer;
+
+/// Writes the contents of the frame buffer to a timestamped text file in the working directory,
+/// returning the path it was saved to
+pub fn save_screenshot(buffer: &FrameBuffer) -> io::Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|since_epoch| since_epoch.as_millis()).unwrap_or(0);
+    let path = PathBuf::from(format!("cursed-maze-{}.txt", timestamp));
+
+    fs::write(&path, buffer.to_text())?;
+
+    return Ok(path);
+}
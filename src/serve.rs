@@ -0,0 +1,338 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use super::clock::{Clock, SystemClock};
+use super::input::{MOVE_SPEED, TURN_SPEED};
+use super::render::{frame_sleep, RenderStyle, Scene, RENDER_FPS};
+use super::world::camera::Camera;
+use super::world::collision::{move_with_collision, DEFAULT_BODY_RADIUS};
+use super::world::pillar::Wall;
+
+pub const DEFAULT_PORT: u16 = 2323;
+
+/// Number of frames of movement a single key press is worth, since remote terminals send
+/// repeated presses rather than reporting held keys
+const KEYPRESS_FRAMES: f64 = 3.0;
+const DEFAULT_ROWS: i32 = 24;
+const DEFAULT_COLS: i32 = 80;
+/// The largest window a client can ask for, so a bogus size can't make the server allocate
+/// enormous frame buffers
+const MAX_WINDOW_SIZE: i32 = 500;
+/// The most games served at once. Anyone connecting while the server is full is turned away, so
+/// connections can't use up the host's threads and memory.
+const MAX_SESSIONS: usize = 16;
+const SERVER_FULL_MESSAGE: &str = "The maze is full, try again later\r\n";
+
+// Telnet protocol bytes
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const OPT_ECHO: u8 = 1;
+const OPT_SUPPRESS_GO_AHEAD: u8 = 3;
+const OPT_NAWS: u8 = 31;
+
+/// Asks the client to stop echoing, send characters as they're typed, and report its window size
+pub const TELNET_SETUP: [u8; 9] = [IAC, WILL, OPT_ECHO, IAC, WILL, OPT_SUPPRESS_GO_AHEAD, IAC, DO, OPT_NAWS];
+pub const CLEAR_SCREEN: &str = "\x1b[2J";
+pub const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// Something the remote player did
+#[derive(Debug, Eq, PartialEq)]
+pub enum RemoteEvent {
+    Forward,
+    Back,
+    TurnLeft,
+    TurnRight,
+    Quit,
+    Resize { rows: i32, cols: i32 },
+}
+
+#[derive(Copy, Clone)]
+enum ParseState {
+    Text,
+    Command,
+    Negotiation,
+    Subnegotiation,
+    SubnegotiationCommand,
+    Escape,
+    ControlSequence,
+}
+
+/// Turns the raw bytes sent by a telnet client into player events, skipping protocol negotiation
+pub struct RemoteInput {
+    state: ParseState,
+    subnegotiation: Vec<u8>,
+}
+
+impl RemoteInput {
+    pub fn new() -> RemoteInput {
+        RemoteInput { state: ParseState::Text, subnegotiation: Vec::new() }
+    }
+
+    /// Feeds received bytes through the parser, returning any events they completed
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<RemoteEvent> {
+        let mut events = Vec::new();
+
+        for &byte in bytes {
+            let (next_state, event) = match (self.state, byte) {
+                (ParseState::Text, IAC) => (ParseState::Command, None),
+                (ParseState::Text, 0x1b) => (ParseState::Escape, None),
+                (ParseState::Text, _) => (ParseState::Text, key_event(byte)),
+                (ParseState::Command, WILL) | (ParseState::Command, WONT) | (ParseState::Command, DO) | (ParseState::Command, DONT) => (ParseState::Negotiation, None),
+                (ParseState::Command, SB) => {
+                    self.subnegotiation.clear();
+                    (ParseState::Subnegotiation, None)
+                }
+                (ParseState::Command, _) | (ParseState::Negotiation, _) => (ParseState::Text, None),
+                (ParseState::Subnegotiation, IAC) => (ParseState::SubnegotiationCommand, None),
+                (ParseState::Subnegotiation, _) => {
+                    self.subnegotiation.push(byte);
+                    (ParseState::Subnegotiation, None)
+                }
+                (ParseState::SubnegotiationCommand, SE) => (ParseState::Text, self.window_size_event()),
+                (ParseState::SubnegotiationCommand, _) => {
+                    // A doubled IAC is a literal 255 inside the subnegotiation
+                    self.subnegotiation.push(byte);
+                    (ParseState::Subnegotiation, None)
+                }
+                (ParseState::Escape, b'[') | (ParseState::Escape, b'O') => (ParseState::ControlSequence, None),
+                (ParseState::Escape, _) => (ParseState::Text, Some(RemoteEvent::Quit)),
+                (ParseState::ControlSequence, b'A') => (ParseState::Text, Some(RemoteEvent::Forward)),
+                (ParseState::ControlSequence, b'B') => (ParseState::Text, Some(RemoteEvent::Back)),
+                (ParseState::ControlSequence, b'C') => (ParseState::Text, Some(RemoteEvent::TurnRight)),
+                (ParseState::ControlSequence, b'D') => (ParseState::Text, Some(RemoteEvent::TurnLeft)),
+                (ParseState::ControlSequence, _) => (ParseState::Text, None),
+            };
+
+            self.state = next_state;
+            if let Some(event) = event {
+                events.push(event);
+            }
+        }
+
+        return events;
+    }
+
+    /// Reads a window size report, ignoring empty windows and clamping huge ones
+    fn window_size_event(&self) -> Option<RemoteEvent> {
+        let (rows, cols) = match self.subnegotiation.as_slice() {
+            [OPT_NAWS, width_high, width_low, height_high, height_low] => (
+                i32::from(u16::from_be_bytes([*height_high, *height_low])),
+                i32::from(u16::from_be_bytes([*width_high, *width_low])),
+            ),
+            _ => return None,
+        };
+        if rows == 0 || cols == 0 {
+            return None;
+        }
+
+        return Some(RemoteEvent::Resize { rows: rows.min(MAX_WINDOW_SIZE), cols: cols.min(MAX_WINDOW_SIZE) });
+    }
+}
+
+fn key_event(byte: u8) -> Option<RemoteEvent> {
+    match byte {
+        b'w' | b'W' => Some(RemoteEvent::Forward),
+        b's' | b'S' => Some(RemoteEvent::Back),
+        b'a' | b'A' => Some(RemoteEvent::TurnLeft),
+        b'd' | b'D' => Some(RemoteEvent::TurnRight),
+        b'q' | b'Q' | 3 => Some(RemoteEvent::Quit),
+        _ => None,
+    }
+}
+
+/// Listens on the given port and runs an independent game in the given world for every client
+/// that connects, drawn in the given style
+pub fn serve(port: u16, walls: &[Wall], style: RenderStyle) -> std::io::Result<()> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    println!("Serving cursed-maze on port {}", port);
+
+    let active_sessions = AtomicUsize::new(0);
+    thread::scope(|session_scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(mut stream) if !claim_session(&active_sessions) => {
+                    let _ = stream.write_all(SERVER_FULL_MESSAGE.as_bytes());
+                }
+                Ok(stream) => {
+                    let active_sessions = &active_sessions;
+                    session_scope.spawn(move || {
+                        let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+                        if let Err(err) = run_session(stream, walls, style, &SystemClock::new()) {
+                            eprintln!("Session with {} ended: {}", peer, err);
+                        }
+                        active_sessions.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+                Err(err) => eprintln!("Failed to accept connection: {}", err),
+            }
+        }
+    });
+
+    return Ok(());
+}
+
+/// Counts a new session as active unless the server is already running as many as it can
+fn claim_session(active_sessions: &AtomicUsize) -> bool {
+    return active_sessions.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| (active < MAX_SESSIONS).then_some(active + 1)).is_ok();
+}
+
+/// Plays one game over the connection until the client quits or disconnects, with time passing on
+/// the given clock
+fn run_session(mut stream: TcpStream, walls: &[Wall], style: RenderStyle, clock: &impl Clock) -> std::io::Result<()> {
+    stream.set_nonblocking(true)?;
+    stream.write_all(&TELNET_SETUP)?;
+    stream.write_all(format!("{}{}", HIDE_CURSOR, CLEAR_SCREEN).as_bytes())?;
+
+    let mut input = RemoteInput::new();
+    let mut scene = Scene::with_dimensions(DEFAULT_ROWS, DEFAULT_COLS);
+    scene.set_style(style);
+    let mut cam = Camera::new();
+    let mut read_buffer = [0u8; 256];
+
+    loop {
+        let mut forward_change = 0.0;
+        let mut angle_change = 0.0;
+
+        loop {
+            let bytes_read = match stream.read(&mut read_buffer) {
+                Ok(0) => return Ok(()),
+                Ok(bytes_read) => bytes_read,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => return Err(err),
+            };
+
+            for event in input.parse(&read_buffer[..bytes_read]) {
+                match event {
+                    RemoteEvent::Forward => forward_change += KEYPRESS_FRAMES * MOVE_SPEED / RENDER_FPS,
+                    RemoteEvent::Back => forward_change -= KEYPRESS_FRAMES * MOVE_SPEED / RENDER_FPS,
+                    RemoteEvent::TurnLeft => angle_change += KEYPRESS_FRAMES * TURN_SPEED / RENDER_FPS,
+                    RemoteEvent::TurnRight => angle_change -= KEYPRESS_FRAMES * TURN_SPEED / RENDER_FPS,
+                    RemoteEvent::Resize { rows, cols } => {
+                        scene = Scene::with_dimensions(rows, cols);
+                        scene.set_style(style);
+                        stream.write_all(CLEAR_SCREEN.as_bytes())?;
+                    }
+                    RemoteEvent::Quit => {
+                        stream.set_nonblocking(false)?;
+                        stream.write_all(format!("{}{}", CLEAR_SCREEN, SHOW_CURSOR).as_bytes())?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        cam = move_with_collision(&cam, &cam.update_cam(forward_change, angle_change), walls, DEFAULT_BODY_RADIUS);
+        scene.draw_frame(&cam, walls);
+
+        let frame = scene.frame_buffer().flush_ansi();
+        stream.set_nonblocking(false)?;
+        stream.write_all(frame.as_bytes())?;
+        stream.set_nonblocking(true)?;
+
+        frame_sleep(clock);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+
+    use super::{claim_session, run_session, RemoteEvent, RemoteInput, DEFAULT_COLS, DEFAULT_ROWS, DO, IAC, KEYPRESS_FRAMES, MAX_SESSIONS, MAX_WINDOW_SIZE, OPT_NAWS, SB, SE, WILL};
+    use crate::clock::{Clock, VirtualClock};
+    use crate::input::MOVE_SPEED;
+    use crate::render::{Charset, RenderStyle, Scene, RENDER_FPS};
+    use crate::world::camera::Camera;
+    use crate::world::pillar::{Pillar, Wall};
+
+    #[test]
+    fn reads_keys_and_arrows() {
+        let mut input = RemoteInput::new();
+
+        assert_eq!(vec![RemoteEvent::Forward, RemoteEvent::TurnLeft, RemoteEvent::TurnRight], input.parse(b"w\x1b[D\x1b[C"));
+    }
+
+    #[test]
+    fn skips_negotiation() {
+        let mut input = RemoteInput::new();
+
+        assert_eq!(vec![RemoteEvent::Back], input.parse(&[IAC, WILL, OPT_NAWS, IAC, DO, 3, b's']));
+    }
+
+    #[test]
+    fn reads_window_size() {
+        let mut input = RemoteInput::new();
+        let events = input.parse(&[IAC, SB, OPT_NAWS, 0, 120, 0, 40, IAC, SE]);
+
+        assert_eq!(vec![RemoteEvent::Resize { rows: 40, cols: 120 }], events);
+    }
+
+    #[test]
+    fn clamps_oversized_window_and_ignores_empty_one() {
+        let mut input = RemoteInput::new();
+        // A 65535 x 65535 window, with each 255 byte doubled as telnet requires
+        let huge = input.parse(&[IAC, SB, OPT_NAWS, IAC, IAC, IAC, IAC, IAC, IAC, IAC, IAC, IAC, SE]);
+        let empty = input.parse(&[IAC, SB, OPT_NAWS, 0, 0, 0, 40, IAC, SE]);
+
+        assert_eq!(vec![RemoteEvent::Resize { rows: MAX_WINDOW_SIZE, cols: MAX_WINDOW_SIZE }], huge);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn session_runs_on_virtual_clock() {
+        let pillars = [Pillar::at(6.0, -3.0), Pillar::at(6.0, 3.0)];
+        let walls = [Wall::from_pillars(&pillars[0], &pillars[1])];
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        // Sent before the session starts so it all arrives in the first frame
+        client.write_all(b"ww").unwrap();
+        let (server_stream, _) = listener.accept().unwrap();
+
+        let style = RenderStyle { charset: Charset::BLOCKS, ..RenderStyle::default() };
+        let mut expected_scene = Scene::with_dimensions(DEFAULT_ROWS, DEFAULT_COLS);
+        expected_scene.set_style(style);
+        expected_scene.draw_frame(&Camera::new().update_cam(2.0 * KEYPRESS_FRAMES * MOVE_SPEED / RENDER_FPS, 0.0), &walls);
+        let expected_frame = expected_scene.frame_buffer().flush_ansi();
+
+        // Quit once the first frame has come through
+        let client_thread = thread::spawn(move || {
+            let mut received = Vec::new();
+            let mut read_buffer = [0u8; 4096];
+            while !String::from_utf8_lossy(&received).contains(&expected_frame) {
+                let bytes_read = client.read(&mut read_buffer).unwrap();
+                assert!(bytes_read > 0, "session ended before sending the expected frame");
+                received.extend_from_slice(&read_buffer[..bytes_read]);
+            }
+            client.write_all(b"q").unwrap();
+            client.read_to_end(&mut received).unwrap();
+        });
+
+        let clock = VirtualClock::new();
+        run_session(server_stream, &walls, style, &clock).unwrap();
+        client_thread.join().unwrap();
+
+        assert!(!clock.elapsed().is_zero());
+    }
+
+    #[test]
+    fn turns_sessions_away_once_full() {
+        let active_sessions = AtomicUsize::new(0);
+        for _ in 0..MAX_SESSIONS {
+            assert!(claim_session(&active_sessions));
+        }
+        assert!(!claim_session(&active_sessions));
+
+        active_sessions.fetch_sub(1, Ordering::SeqCst);
+        assert!(claim_session(&active_sessions));
+    }
+}
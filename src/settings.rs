@@ -0,0 +1,249 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::input::BindingProfile;
+use super::render::{Charset, RenderStyle, CELL_ASPECT_RANGE};
+use super::serve::DEFAULT_PORT;
+
+pub const USAGE: &str = "Usage: cursed-maze [--record-cast FILE] [--spectator-port PORT]
+                   [--control-socket PATH] [--high-contrast]
+                   [--reduced-motion] [--charset classic|blocks|dots]
+                   [--door-frames] [--soft-edges] [--particles] [--noclip]
+                   [--body-radius UNITS] [--cell-aspect RATIO]
+                   [--day-length SECONDS] [--diagnostics FILE]
+                   [--bindings default|left-handed|ijkl] [--quiet]
+                   [--hardcore] [--theme FILE|surprise]
+       cursed-maze serve [PORT]
+       cursed-maze split-screen";
+
+/// What the program should do when it starts
+#[derive(Debug, Default, Eq, PartialEq)]
+pub enum Mode {
+    #[default]
+    Play,
+    Serve { port: u16 },
+    /// Two players sharing the keyboard, each with half the screen
+    SplitScreen,
+}
+
+/// Where the render theme comes from, when one is chosen
+#[derive(Debug, Eq, PartialEq)]
+pub enum ThemeChoice {
+    /// A randomly picked built-in look
+    Surprise,
+    /// A theme pack file
+    File(PathBuf),
+}
+
+/// Options chosen on the command line
+#[derive(Debug, Default, PartialEq)]
+pub struct Settings {
+    pub mode: Mode,
+    pub cast_path: Option<PathBuf>,
+    pub spectator_port: Option<u16>,
+    pub control_socket_path: Option<PathBuf>,
+    pub render_style: RenderStyle,
+    pub theme: Option<ThemeChoice>,
+    pub reduced_motion: bool,
+    pub binding_profile: BindingProfile,
+    /// Never ring the terminal bell
+    pub quiet: bool,
+    /// No map or position readout, and a shorter draw distance
+    pub hardcore: bool,
+    /// Draw dust drifting through the air
+    pub particles: bool,
+    /// How close the camera can get to walls, if not the default
+    pub body_radius: Option<f64>,
+    /// The height of the terminal's cells over their width, if not the usual two to one
+    pub cell_aspect: Option<f64>,
+    /// Debug mode letting the camera pass through walls and move faster
+    pub noclip: bool,
+    /// How long a full day and night cycle of lighting takes, if the light should change at all
+    pub day_length: Option<Duration>,
+    /// Where to write a diagnostics report when the game ends
+    pub diagnostics_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum SettingsErr {
+    UnknownArgument(String),
+    MissingValue(String),
+    InvalidPort(String),
+    UnknownCharset(String),
+    InvalidDuration(String),
+    UnknownBindingProfile(String),
+    InvalidDistance(String),
+    InvalidRatio(String),
+}
+
+impl fmt::Display for SettingsErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsErr::UnknownArgument(arg) => write!(f, "unknown argument '{}'", arg),
+            SettingsErr::MissingValue(arg) => write!(f, "'{}' needs a value", arg),
+            SettingsErr::InvalidPort(port) => write!(f, "'{}' is not a valid port", port),
+            SettingsErr::UnknownCharset(name) => write!(f, "there is no charset called '{}'", name),
+            SettingsErr::InvalidDuration(seconds) => write!(f, "'{}' is not a valid number of seconds", seconds),
+            SettingsErr::UnknownBindingProfile(name) => write!(f, "there is no binding profile called '{}'", name),
+            SettingsErr::InvalidDistance(distance) => write!(f, "'{}' is not a valid distance", distance),
+            SettingsErr::InvalidRatio(ratio) => write!(f, "'{}' is not a valid ratio", ratio),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads settings from command line arguments, not including the program name
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Settings, SettingsErr> {
+        let mut settings = Settings::default();
+        let mut args = args.into_iter().peekable();
+
+        match args.peek().map(String::as_str) {
+            Some("serve") => {
+                args.next();
+                let port = match args.next() {
+                    Some(port) => port.parse::<u16>().map_err(|_| SettingsErr::InvalidPort(port))?,
+                    None => DEFAULT_PORT,
+                };
+                settings.mode = Mode::Serve { port };
+            }
+            Some("split-screen") => {
+                args.next();
+                settings.mode = Mode::SplitScreen;
+            }
+            _ => {}
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record-cast" => settings.cast_path = Some(PathBuf::from(args.next().ok_or(SettingsErr::MissingValue(arg))?)),
+                "--spectator-port" => {
+                    let port = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    settings.spectator_port = Some(port.parse::<u16>().map_err(|_| SettingsErr::InvalidPort(port))?);
+                }
+                "--control-socket" => settings.control_socket_path = Some(PathBuf::from(args.next().ok_or(SettingsErr::MissingValue(arg))?)),
+                "--high-contrast" => settings.render_style.high_contrast = true,
+                "--reduced-motion" => settings.reduced_motion = true,
+                "--charset" => {
+                    let name = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    settings.render_style.charset = Charset::named(&name).ok_or(SettingsErr::UnknownCharset(name))?;
+                }
+                "--door-frames" => settings.render_style.door_frames = true,
+                "--soft-edges" => settings.render_style.soft_edges = true,
+                "--particles" => settings.particles = true,
+                "--bindings" => {
+                    let name = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    settings.binding_profile = BindingProfile::named(&name).ok_or(SettingsErr::UnknownBindingProfile(name))?;
+                }
+                "--quiet" => settings.quiet = true,
+                "--hardcore" => settings.hardcore = true,
+                "--theme" => {
+                    let theme = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    settings.theme = Some(if theme == "surprise" { ThemeChoice::Surprise } else { ThemeChoice::File(PathBuf::from(theme)) });
+                }
+                "--body-radius" => {
+                    let radius = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    let parsed_radius = radius.parse::<f64>().ok().filter(|radius| radius.is_finite() && *radius >= 0.0);
+                    settings.body_radius = Some(parsed_radius.ok_or(SettingsErr::InvalidDistance(radius))?);
+                }
+                "--cell-aspect" => {
+                    let ratio = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    let parsed_ratio = ratio.parse::<f64>().ok().filter(|ratio| CELL_ASPECT_RANGE.contains(ratio));
+                    settings.cell_aspect = Some(parsed_ratio.ok_or(SettingsErr::InvalidRatio(ratio))?);
+                }
+                "--noclip" => settings.noclip = true,
+                "--day-length" => {
+                    let seconds = args.next().ok_or(SettingsErr::MissingValue(arg))?;
+                    let day_length = seconds.parse::<f64>().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+                    settings.day_length = Some(day_length.ok_or(SettingsErr::InvalidDuration(seconds))?);
+                }
+                "--diagnostics" => settings.diagnostics_path = Some(PathBuf::from(args.next().ok_or(SettingsErr::MissingValue(arg))?)),
+                _ => return Err(SettingsErr::UnknownArgument(arg)),
+            }
+        }
+
+        return Ok(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::{BindingProfile, Charset, Mode, Settings, SettingsErr, ThemeChoice};
+
+    fn parse(args: &[&str]) -> Result<Settings, SettingsErr> {
+        Settings::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn defaults_to_playing() {
+        assert_eq!(Ok(Settings::default()), parse(&[]));
+    }
+
+    #[test]
+    fn reads_serve_port() {
+        assert_eq!(Mode::Serve { port: 4000 }, parse(&["serve", "4000"]).unwrap().mode);
+        assert_eq!(Err(SettingsErr::InvalidPort("x".to_string())), parse(&["serve", "x"]));
+    }
+
+    #[test]
+    fn reads_split_screen() {
+        assert_eq!(Mode::SplitScreen, parse(&["split-screen", "--charset", "dots"]).unwrap().mode);
+    }
+
+    #[test]
+    fn reads_cast_path() {
+        assert_eq!(Some(PathBuf::from("run.cast")), parse(&["--record-cast", "run.cast"]).unwrap().cast_path);
+        assert_eq!(Err(SettingsErr::MissingValue("--record-cast".to_string())), parse(&["--record-cast"]));
+    }
+
+    #[test]
+    fn reads_high_contrast() {
+        assert!(parse(&["--high-contrast"]).unwrap().render_style.high_contrast);
+    }
+
+    #[test]
+    fn reads_charset() {
+        assert_eq!(Charset::BLOCKS, parse(&["--charset", "blocks"]).unwrap().render_style.charset);
+        assert_eq!(Err(SettingsErr::UnknownCharset("fancy".to_string())), parse(&["--charset", "fancy"]));
+    }
+
+    #[test]
+    fn reads_spectator_port() {
+        assert_eq!(Some(9000), parse(&["--spectator-port", "9000"]).unwrap().spectator_port);
+    }
+
+    #[test]
+    fn reads_day_length() {
+        assert_eq!(Some(Duration::from_secs(90)), parse(&["--day-length", "90"]).unwrap().day_length);
+        assert_eq!(Err(SettingsErr::InvalidDuration("-5".to_string())), parse(&["--day-length", "-5"]));
+    }
+
+    #[test]
+    fn reads_binding_profile() {
+        assert_eq!(BindingProfile::Ijkl, parse(&["--bindings", "ijkl"]).unwrap().binding_profile);
+        assert_eq!(Err(SettingsErr::UnknownBindingProfile("dvorak".to_string())), parse(&["--bindings", "dvorak"]));
+    }
+
+    #[test]
+    fn reads_theme() {
+        assert_eq!(Some(ThemeChoice::Surprise), parse(&["--theme", "surprise"]).unwrap().theme);
+        assert_eq!(Some(ThemeChoice::File(PathBuf::from("ice.json"))), parse(&["--theme", "ice.json"]).unwrap().theme);
+    }
+
+    #[test]
+    fn reads_body_radius() {
+        assert_eq!(Some(0.25), parse(&["--body-radius", "0.25"]).unwrap().body_radius);
+        assert_eq!(Err(SettingsErr::InvalidDistance("-1".to_string())), parse(&["--body-radius", "-1"]));
+    }
+
+    #[test]
+    fn reads_cell_aspect() {
+        assert_eq!(Some(2.5), parse(&["--cell-aspect", "2.5"]).unwrap().cell_aspect);
+        assert_eq!(Err(SettingsErr::InvalidRatio("0".to_string())), parse(&["--cell-aspect", "0"]));
+        assert_eq!(Err(SettingsErr::InvalidRatio("1e-300".to_string())), parse(&["--cell-aspect", "1e-300"]));
+        assert_eq!(Err(SettingsErr::InvalidRatio("1e300".to_string())), parse(&["--cell-aspect", "1e300"]));
+    }
+}
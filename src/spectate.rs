@@ -0,0 +1,58 @@
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use super::curses_util::ansi_mirror::AnsiMirror;
+use super::curses_util::frame_buffer::FrameBuffer;
+use super::serve::{CLEAR_SCREEN, HIDE_CURSOR, TELNET_SETUP};
+
+/// How long a spectator may hold up a frame before being dropped
+const SPECTATOR_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+struct Spectator {
+    stream: TcpStream,
+    mirror: AnsiMirror,
+}
+
+/// Accepts read-only connections and mirrors the local player's view to each of them
+pub struct SpectatorServer {
+    listener: TcpListener,
+    spectators: Vec<Spectator>,
+}
+
+impl SpectatorServer {
+    /// Starts listening for spectators on the given port
+    pub fn bind(port: u16) -> io::Result<SpectatorServer> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+
+        return Ok(SpectatorServer { listener, spectators: Vec::new() });
+    }
+
+    /// Accepts any waiting spectators and sends everyone the changes in the latest frame.
+    /// Spectators who disconnect or fall behind are dropped.
+    pub fn broadcast(&mut self, buffer: &FrameBuffer) {
+        // Stops once nobody else is waiting or the listener fails; either way, try again next frame
+        while let Ok((stream, _)) = self.listener.accept() {
+            if let Ok(spectator) = Spectator::greet(stream) {
+                self.spectators.push(spectator);
+            }
+        }
+
+        self.spectators.retain_mut(|spectator| {
+            let update = spectator.mirror.update(buffer);
+            update.is_empty() || spectator.stream.write_all(update.as_bytes()).is_ok()
+        });
+    }
+}
+
+impl Spectator {
+    fn greet(mut stream: TcpStream) -> io::Result<Spectator> {
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(SPECTATOR_WRITE_TIMEOUT))?;
+        stream.write_all(&TELNET_SETUP)?;
+        stream.write_all(format!("{}{}", HIDE_CURSOR, CLEAR_SCREEN).as_bytes())?;
+
+        return Ok(Spectator { stream, mirror: AnsiMirror::new() });
+    }
+}
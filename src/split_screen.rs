@@ -0,0 +1,110 @@
+use device_query::DeviceState;
+use ncurses::*;
+
+use super::clock::SystemClock;
+use super::curses_util::frame_buffer::{CellStyle, FrameBuffer};
+use super::curses_util::lifecycle::CursesHandle;
+use super::input::{player_one_bindings, player_two_bindings, InputState, ProgramCommand, TurnMode};
+use super::overlay::draw_enlarge_message;
+use super::render::{frame_sleep, Scene};
+use super::settings::Settings;
+use super::world::camera::Camera;
+use super::world::collision::{move_with_collision, DEFAULT_BODY_RADIUS};
+use super::world::pillar::Wall;
+
+/// The smallest terminal two views fit side by side in. Smaller terminals are asked to enlarge.
+const MIN_ROWS: i32 = 12;
+const MIN_COLS: i32 = 60;
+const DIVIDER_CHAR: char = '|';
+
+/// Where each player's view goes on a screen of the given width, as the left column and width of
+/// each half with a one column divider between them
+fn viewport_columns(screen_cols: i32) -> [(i32, i32); 2] {
+    let left_cols = (screen_cols - 1) / 2;
+    let right_cols = screen_cols - 1 - left_cols;
+
+    return [(0, left_cols), (left_cols + 1, right_cols)];
+}
+
+/// Plays the maze with two players on one keyboard, each with their own half of the terminal.
+/// Player one moves with WASD and player two with the arrow keys.
+pub fn play_split_screen(walls: &[Wall], settings: &Settings) {
+    // When the curses handle falls out of scope it'll turn off curses
+    let _curse_handle = CursesHandle::create();
+
+    let mut max_row = 0;
+    let mut max_col = 0;
+    getmaxyx(stdscr(), &mut max_row, &mut max_col);
+
+    let clock = SystemClock::new();
+    let input = DeviceState::new();
+    let turn_mode = if settings.reduced_motion { TurnMode::Snap } else { TurnMode::Smooth };
+    let body_radius = if settings.noclip { None } else { Some(settings.body_radius.unwrap_or(DEFAULT_BODY_RADIUS)) };
+    let mut players = [player_one_bindings(), player_two_bindings()].map(|bindings| InputState::new(bindings, turn_mode));
+    let mut cams = [Camera::new(), Camera::new()];
+
+    let mut screen = FrameBuffer::with_dimensions(max_row, max_col);
+    let mut scenes = viewport_columns(max_col).map(|(_, cols)| Scene::with_dimensions(max_row, cols));
+    for scene in &mut scenes {
+        scene.set_style(settings.render_style);
+        if let Some(cell_aspect) = settings.cell_aspect {
+            scene.set_cell_aspect(cell_aspect);
+        }
+    }
+
+    loop {
+        let mut quit = false;
+        for (player, cam) in players.iter_mut().zip(cams.iter_mut()) {
+            let (new_cam, command) = player.move_camera(&input, cam);
+            *cam = body_radius.map_or(new_cam, |radius| move_with_collision(cam, &new_cam, walls, radius));
+            quit |= command == ProgramCommand::Quit;
+        }
+
+        let (old_row, old_col) = (max_row, max_col);
+        getmaxyx(stdscr(), &mut max_row, &mut max_col);
+        if (max_row, max_col) != (old_row, old_col) {
+            screen = FrameBuffer::with_dimensions(max_row, max_col);
+            for (scene, (_, cols)) in scenes.iter_mut().zip(viewport_columns(max_col)) {
+                scene.resize(max_row, cols);
+            }
+            clear();
+        }
+
+        screen.clear();
+        if max_row < MIN_ROWS || max_col < MIN_COLS {
+            draw_enlarge_message(&mut screen, MIN_ROWS, MIN_COLS);
+        } else {
+            for (player_idx, (scene, (left_col, _))) in scenes.iter_mut().zip(viewport_columns(max_col)).enumerate() {
+                scene.draw_frame(&cams[player_idx], walls);
+                screen.draw_buffer(0, left_col, scene.frame_buffer());
+                screen.set_style(CellStyle::Reverse);
+                screen.put_str(0, left_col, &format!("P{}", player_idx + 1));
+            }
+
+            let divider_col = viewport_columns(max_col)[1].0 - 1;
+            screen.set_style(CellStyle::Plain);
+            for row in 0..max_row {
+                screen.put_char(row, divider_col, DIVIDER_CHAR);
+            }
+        }
+        screen.flush();
+        refresh();
+
+        frame_sleep(&clock);
+
+        if quit {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::viewport_columns;
+
+    #[test]
+    fn splits_screen_around_divider() {
+        assert_eq!([(0, 39), (40, 40)], viewport_columns(80));
+        assert_eq!([(0, 40), (41, 40)], viewport_columns(81));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use super::render::{Charset, RenderStyle};
+
+/// The charsets "surprise me" picks between
+const SURPRISE_CHARSETS: [Charset; 3] = [Charset::CLASSIC, Charset::BLOCKS, Charset::DOTS];
+
+/// A set of render options loaded from a JSON file, so new looks don't need code changes
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Theme {
+    wall_edge: char,
+    wall_fill: char,
+    /// Characters softening sloped edges, if the theme picks its own
+    #[serde(default)]
+    soft_edge_heavy: Option<char>,
+    #[serde(default)]
+    soft_edge_light: Option<char>,
+    /// Characters for nearby and distant dust motes, if the theme picks its own
+    #[serde(default)]
+    particle_near: Option<char>,
+    #[serde(default)]
+    particle_far: Option<char>,
+    #[serde(default)]
+    high_contrast: bool,
+    #[serde(default)]
+    door_frames: bool,
+    #[serde(default)]
+    soft_edges: bool,
+}
+
+impl Theme {
+    /// Reads a theme pack from a JSON file
+    pub fn load(path: &Path) -> io::Result<Theme> {
+        return Ok(serde_json::from_str(&fs::read_to_string(path)?)?);
+    }
+
+    /// Picks one of the built-in charsets using the given seed
+    pub fn surprise(seed: u64) -> Theme {
+        let charset = SURPRISE_CHARSETS[(seed % SURPRISE_CHARSETS.len() as u64) as usize];
+
+        return Theme {
+            wall_edge: charset.wall_edge,
+            wall_fill: charset.wall_fill,
+            soft_edge_heavy: Some(charset.soft_edge_heavy),
+            soft_edge_light: Some(charset.soft_edge_light),
+            particle_near: Some(charset.particle_near),
+            particle_far: Some(charset.particle_far),
+            high_contrast: false,
+            door_frames: false,
+            soft_edges: false,
+        };
+    }
+
+    /// Lays the theme over the style chosen with command line flags. The theme's characters
+    /// replace the charset's, and its options are added to any turned on by flags.
+    pub fn apply_to(&self, style: RenderStyle) -> RenderStyle {
+        return RenderStyle {
+            high_contrast: style.high_contrast || self.high_contrast,
+            charset: Charset {
+                wall_edge: self.wall_edge,
+                wall_fill: self.wall_fill,
+                soft_edge_heavy: self.soft_edge_heavy.unwrap_or(style.charset.soft_edge_heavy),
+                soft_edge_light: self.soft_edge_light.unwrap_or(style.charset.soft_edge_light),
+                particle_near: self.particle_near.unwrap_or(style.charset.particle_near),
+                particle_far: self.particle_far.unwrap_or(style.charset.particle_far),
+            },
+            door_frames: style.door_frames || self.door_frames,
+            soft_edges: style.soft_edges || self.soft_edges,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Theme;
+    use crate::render::{Charset, RenderStyle};
+
+    #[test]
+    fn applies_theme_over_flags() {
+        let theme: Theme = serde_json::from_str(r#"{"wall_edge": "%", "wall_fill": "~", "soft_edge_light": ",", "particle_near": "@", "soft_edges": true}"#).unwrap();
+        let flag_style = RenderStyle { high_contrast: true, charset: Charset::DOTS, ..RenderStyle::default() };
+
+        let style = theme.apply_to(flag_style);
+        let expected_charset = Charset { wall_edge: '%', wall_fill: '~', soft_edge_light: ',', particle_near: '@', ..Charset::DOTS };
+        assert_eq!(expected_charset, style.charset);
+        assert!(style.high_contrast && style.soft_edges && !style.door_frames);
+    }
+}
@@ -72,11 +72,48 @@ impl Camera {
         return (-half_fov_angle..half_fov_angle).contains(&view_angle_from_center) && self.distance_to(other) < self.horizon_distance
     }
 
+    /// Returns true if the straight line between the two entities crosses either edge of the view
+    /// frustum before the horizon, which happens when a line passes through the view with both
+    /// ends out of sight
+    pub fn view_edges_cross(&self, start: &impl WorldEntity, end: &impl WorldEntity) -> bool {
+        let line_x = end.x_pos() - start.x_pos();
+        let line_y = end.y_pos() - start.y_pos();
+        let start_x = start.x_pos() - self.x_pos;
+        let start_y = start.y_pos() - self.y_pos;
+        let half_fov_angle = self.fov_angle / 2.0;
+
+        for edge_angle in [self.facing_direction - half_fov_angle, self.facing_direction + half_fov_angle] {
+            let (edge_y, edge_x) = edge_angle.sin_cos();
+            let denominator = edge_x * line_y - edge_y * line_x;
+            if denominator == 0.0 {
+                continue;
+            }
+
+            // Solve for where the edge ray and the line meet: distance along the ray and fraction along the line
+            let ray_distance = (start_x * line_y - start_y * line_x) / denominator;
+            let line_fraction = (start_x * edge_y - start_y * edge_x) / denominator;
+            if ray_distance > 0.0 && ray_distance < self.horizon_distance && (0.0..=1.0).contains(&line_fraction) {
+                return true;
+            }
+        }
+
+        return false;
+    }
+
     /// Returns true if the camera can see the other entity using the entity's implementation
     pub fn can_see_viewable(&self, other: &impl ViewableEntity) -> bool {
         other.in_camera_view(self)
     }
 
+    /// Returns a copy of the camera moved to the given position, still facing the same way
+    pub fn at_position(&self, x_pos: f64, y_pos: f64) -> Camera {
+        let mut cam_copy = *self;
+        cam_copy.x_pos = x_pos;
+        cam_copy.y_pos = y_pos;
+
+        return cam_copy;
+    }
+
     /// Returns an updated camera, moved forward diff_forward and rotated diff_angle
     pub fn update_cam(&self, diff_forward: f64, diff_angle: f64) -> Camera {
         let new_angle = normalize_range(self.facing_direction + diff_angle, 0.0..TWO_PI);
@@ -84,7 +121,7 @@ impl Camera {
         let x_change = diff_forward * new_angle.cos();
         let y_change = diff_forward * new_angle.sin();
 
-        let mut cam_copy = self.clone();
+        let mut cam_copy = *self;
         cam_copy.x_pos = self.x_pos + x_change;
         cam_copy.y_pos = self.y_pos + y_change;
         cam_copy.facing_direction = new_angle;
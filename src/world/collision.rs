@@ -0,0 +1,92 @@
+use super::camera::Camera;
+use super::pillar::{Pillar, Wall};
+use super::util::segments_cross;
+use super::world_entity::WorldEntity;
+
+/// How close the camera can get to a wall unless told otherwise, in world units
+pub const DEFAULT_BODY_RADIUS: f64 = 0.5;
+
+/// Moves the camera from one position to another without letting it get closer than the body
+/// radius to any wall. Blocked moves slide along the wall where they can. Moves which don't bring
+/// the camera any closer to a wall are always allowed, so a camera that starts too close can
+/// still back away. Long moves can't pass through a wall in one step either.
+pub fn move_with_collision(from: &Camera, to: &Camera, walls: &[Wall], body_radius: f64) -> Camera {
+    let blocked = |candidate: &Camera| {
+        walls.iter().any(|wall| {
+            let distance = wall.distance_from(candidate);
+            (distance < body_radius && distance < wall.distance_from(from)) || crosses_wall(from, candidate, wall)
+        })
+    };
+
+    if !blocked(to) {
+        return *to;
+    }
+
+    // Try keeping just one axis of the move so the camera slides along the wall
+    let x_only = to.at_position(to.x_pos(), from.y_pos());
+    if !blocked(&x_only) {
+        return x_only;
+    }
+    let y_only = to.at_position(from.x_pos(), to.y_pos());
+    if !blocked(&y_only) {
+        return y_only;
+    }
+
+    return to.at_position(from.x_pos(), from.y_pos());
+}
+
+/// Whether the straight path between two camera positions passes through the given wall
+fn crosses_wall(from: &Camera, to: &Camera, wall: &Wall) -> bool {
+    let pillar_position = |pillar: &Pillar| (pillar.x_pos(), pillar.y_pos());
+
+    return segments_cross((from.x_pos(), from.y_pos()), (to.x_pos(), to.y_pos()), pillar_position(wall.pillar1()), pillar_position(wall.pillar2()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::move_with_collision;
+    use crate::world::camera::Camera;
+    use crate::world::pillar::{Pillar, Wall};
+    use crate::world::world_entity::WorldEntity;
+
+    #[test]
+    fn stops_short_of_wall() {
+        let pillars = [Pillar::at(1.0, -2.0), Pillar::at(1.0, 2.0)];
+        let walls = [Wall::from_pillars(&pillars[0], &pillars[1])];
+        let from = Camera::new();
+
+        let moved = move_with_collision(&from, &from.update_cam(0.8, 0.0), &walls, 0.5);
+        assert_eq!(0.0, moved.x_pos());
+    }
+
+    #[test]
+    fn slides_along_wall() {
+        let pillars = [Pillar::at(1.0, -2.0), Pillar::at(1.0, 2.0)];
+        let walls = [Wall::from_pillars(&pillars[0], &pillars[1])];
+        let from = Camera::new();
+
+        let moved = move_with_collision(&from, &from.update_cam(0.8, std::f64::consts::FRAC_PI_4), &walls, 0.5);
+        assert_eq!(0.0, moved.x_pos());
+        assert!(moved.y_pos() > 0.5);
+    }
+
+    #[test]
+    fn long_move_cannot_tunnel_through_wall() {
+        let pillars = [Pillar::at(1.0, -2.0), Pillar::at(1.0, 2.0)];
+        let walls = [Wall::from_pillars(&pillars[0], &pillars[1])];
+        let from = Camera::new();
+
+        let moved = move_with_collision(&from, &from.update_cam(50.0, 0.0), &walls, 0.5);
+        assert!(moved.x_pos() < 1.0);
+    }
+
+    #[test]
+    fn lets_camera_back_away() {
+        let pillars = [Pillar::at(0.2, -2.0), Pillar::at(0.2, 2.0)];
+        let walls = [Wall::from_pillars(&pillars[0], &pillars[1])];
+        let from = Camera::new();
+
+        let moved = move_with_collision(&from, &from.update_cam(-0.1, 0.0), &walls, 0.5);
+        assert!(moved.x_pos() < 0.0);
+    }
+}
@@ -0,0 +1,22 @@
+/// A short marking painted onto the surface of a wall, such as a sign or an arrow
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decal {
+    text: String,
+    along_wall: f64, // Fraction of the way from the wall's first pillar to its second
+}
+
+impl Decal {
+    /// Creates a decal centred the given fraction of the way along its wall
+    pub fn at(text: &str, along_wall: f64) -> Decal {
+        Decal { text: text.to_string(), along_wall: along_wall.clamp(0.0, 1.0) }
+    }
+
+    /// The characters painted on the wall
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// How far along the wall the decal is centred, from 0.0 at the first pillar to 1.0 at the second
+    pub fn along_wall(&self) -> f64 {
+        self.along_wall
+    }
+}
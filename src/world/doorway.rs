@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use super::pillar::{Pillar, Wall};
+use super::util::segments_cross;
+use super::world_entity::WorldEntity;
+
+/// The widest gap between the end of a wall run and the wall it faces that still counts as a doorway
+const MAX_DOORWAY_WIDTH: f64 = 4.0;
+/// How close a pillar has to be to a gap to count as lying on it, allowing for rounding
+const ON_GAP_DISTANCE: f64 = 1e-9;
+
+/// Marks the ends of wall runs which face another wall across a doorway-sized gap. Ends which
+/// open onto nothing, like those at the edge of the world, aren't doorways.
+pub fn mark_doorways(walls: &mut [Wall]) {
+    let mut walls_at_pillar: HashMap<*const Pillar, usize> = HashMap::new();
+    for wall in walls.iter() {
+        *walls_at_pillar.entry(wall.pillar1()).or_insert(0) += 1;
+        *walls_at_pillar.entry(wall.pillar2()).or_insert(0) += 1;
+    }
+    let is_free_end = |pillar: &Pillar| walls_at_pillar[&(pillar as *const Pillar)] == 1;
+
+    let doorway_ends: Vec<(bool, bool)> = walls.iter()
+        .map(|wall| {
+            let at_pillar1 = is_free_end(wall.pillar1()) && faces_wall_across_gap(wall.pillar1(), wall.pillar2(), walls);
+            let at_pillar2 = is_free_end(wall.pillar2()) && faces_wall_across_gap(wall.pillar2(), wall.pillar1(), walls);
+            (at_pillar1, at_pillar2)
+        })
+        .collect();
+
+    for (wall, (at_pillar1, at_pillar2)) in walls.iter_mut().zip(doorway_ends) {
+        wall.set_doorway_ends(at_pillar1, at_pillar2);
+    }
+}
+
+/// Whether carrying on past the end of a wall, away from its other pillar, reaches another wall
+/// within a doorway's width
+fn faces_wall_across_gap(end: &Pillar, other_end: &Pillar, walls: &[Wall]) -> bool {
+    let (out_x, out_y) = (end.x_pos() - other_end.x_pos(), end.y_pos() - other_end.y_pos());
+    let wall_length = out_x.hypot(out_y);
+    if wall_length == 0.0 {
+        return false;
+    }
+
+    let gap_end = Pillar::at(end.x_pos() + out_x / wall_length * MAX_DOORWAY_WIDTH, end.y_pos() + out_y / wall_length * MAX_DOORWAY_WIDTH);
+    let gap = Wall::from_pillars(end, &gap_end);
+    let pillar_position = |pillar: &Pillar| (pillar.x_pos(), pillar.y_pos());
+
+    // Walls touching the end carry the run on rather than sitting across a gap from it. Walls in
+    // line with the gap never cross it, but one of their pillars lands on it instead.
+    return walls.iter()
+        .filter(|wall| wall.distance_from(end) >= ON_GAP_DISTANCE)
+        .any(|wall| {
+            segments_cross(pillar_position(end), pillar_position(&gap_end), pillar_position(wall.pillar1()), pillar_position(wall.pillar2()))
+                || gap.distance_from(wall.pillar1()) < ON_GAP_DISTANCE
+                || gap.distance_from(wall.pillar2()) < ON_GAP_DISTANCE
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mark_doorways;
+    use crate::world::pillar::{Pillar, Wall};
+
+    #[test]
+    fn marks_ends_facing_across_gap() {
+        let pillars = [Pillar::at(0.0, 0.0), Pillar::at(2.0, 0.0), Pillar::at(3.0, 0.0), Pillar::at(5.0, 0.0)];
+        let mut walls = [Wall::from_pillars(&pillars[0], &pillars[1]), Wall::from_pillars(&pillars[2], &pillars[3])];
+        mark_doorways(&mut walls);
+
+        assert_eq!((false, true), walls[0].doorway_ends());
+        assert_eq!((true, false), walls[1].doorway_ends());
+    }
+
+    #[test]
+    fn leaves_joined_and_world_edge_ends_alone() {
+        let pillars = [Pillar::at(0.0, 0.0), Pillar::at(2.0, 0.0), Pillar::at(2.0, 2.0), Pillar::at(20.0, 0.0), Pillar::at(22.0, 0.0)];
+        let mut walls = [
+            Wall::from_pillars(&pillars[0], &pillars[1]),
+            Wall::from_pillars(&pillars[1], &pillars[2]),
+            Wall::from_pillars(&pillars[3], &pillars[4]),
+        ];
+        mark_doorways(&mut walls);
+
+        assert!(walls.iter().all(|wall| wall.doorway_ends() == (false, false)));
+    }
+}
@@ -1,4 +1,7 @@
 pub mod camera;
+pub mod collision;
+pub mod decal;
+pub mod doorway;
 pub mod world_entity;
 pub mod pillar;
 pub mod util;
\ No newline at end of file
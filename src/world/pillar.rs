@@ -1,4 +1,5 @@
 use super::camera::Camera;
+use super::decal::Decal;
 use super::world_entity::{ViewableEntity, WorldEntity};
 
 pub struct Pillar {
@@ -10,6 +11,9 @@ pub struct Pillar {
 pub struct Wall<'p1, 'p2> {
     pillar1: &'p1 Pillar,
     pillar2: &'p2 Pillar,
+    decal: Option<Decal>,
+    /// Whether a doorway opens beside the first and second pillars
+    doorway_ends: (bool, bool),
 }
 
 impl WorldEntity for Pillar {
@@ -29,13 +33,19 @@ impl Pillar {
 
 impl<'wall> ViewableEntity for Wall<'wall, 'wall> {
     fn in_camera_view(&self, camera: &Camera) -> bool {
-        camera.can_see(self.pillar1) || camera.can_see(self.pillar2)
+        if camera.can_see(self.pillar1) || camera.can_see(self.pillar2) || camera.view_edges_cross(self.pillar1, self.pillar2) {
+            return true;
+        }
+
+        // A wall can also cut across the far end of the view, entering and leaving past the horizon
+        let (closest_x, closest_y) = self.closest_point_to(camera);
+        return camera.can_see(&Pillar::at(closest_x, closest_y));
     }
 }
 
 impl<'p1, 'p2> Wall<'p1, 'p2> {
     pub fn from_pillars(pillar1: &'p1 Pillar, pillar2: &'p2 Pillar) -> Wall<'p1, 'p2> {
-        Wall { pillar1, pillar2 }
+        Wall { pillar1, pillar2, decal: None, doorway_ends: (false, false) }
     }
 
     pub fn pillar1(&self) -> &'p1 Pillar {
@@ -44,4 +54,100 @@ impl<'p1, 'p2> Wall<'p1, 'p2> {
     pub fn pillar2(&self) -> &'p2 Pillar {
         self.pillar2
     }
+
+    /// The marking painted on the wall, if it has one
+    pub fn decal(&self) -> Option<&Decal> {
+        self.decal.as_ref()
+    }
+    /// Paints a marking on the wall, replacing any it already had
+    pub fn set_decal(&mut self, decal: Decal) {
+        self.decal = Some(decal);
+    }
+
+    /// Whether a doorway opens beside the first and second pillars
+    pub fn doorway_ends(&self) -> (bool, bool) {
+        self.doorway_ends
+    }
+    pub fn set_doorway_ends(&mut self, at_pillar1: bool, at_pillar2: bool) {
+        self.doorway_ends = (at_pillar1, at_pillar2);
+    }
+
+    /// The (x, y) position of the point the given fraction of the way from the first pillar to the second
+    pub fn point_along(&self, along_wall: f64) -> (f64, f64) {
+        let x_pos = self.pillar1.x_pos + along_wall * (self.pillar2.x_pos - self.pillar1.x_pos);
+        let y_pos = self.pillar1.y_pos + along_wall * (self.pillar2.y_pos - self.pillar1.y_pos);
+
+        return (x_pos, y_pos);
+    }
+
+    /// The (x, y) position of the point along the wall closest to the other entity
+    pub fn closest_point_to(&self, other: &impl WorldEntity) -> (f64, f64) {
+        let wall_x = self.pillar2.x_pos - self.pillar1.x_pos;
+        let wall_y = self.pillar2.y_pos - self.pillar1.y_pos;
+        let wall_length_squared = wall_x * wall_x + wall_y * wall_y;
+
+        if wall_length_squared == 0.0 {
+            return (self.pillar1.x_pos, self.pillar1.y_pos);
+        }
+
+        // How far along the wall the other entity's projection lands, kept between the pillars
+        let along_wall = ((other.x_pos() - self.pillar1.x_pos) * wall_x + (other.y_pos() - self.pillar1.y_pos) * wall_y) / wall_length_squared;
+
+        return self.point_along(along_wall.clamp(0.0, 1.0));
+    }
+
+    /// The distance from the closest point along the wall to the other entity
+    pub fn distance_from(&self, other: &impl WorldEntity) -> f64 {
+        let (closest_x, closest_y) = self.closest_point_to(other);
+
+        return (other.x_pos() - closest_x).hypot(other.y_pos() - closest_y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pillar, Wall};
+    use crate::world::camera::Camera;
+    use crate::world::world_entity::ViewableEntity;
+
+    #[test]
+    fn finds_closest_point_between_pillars() {
+        let pillars = [Pillar::at(0.0, 0.0), Pillar::at(4.0, 0.0)];
+        let wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+
+        assert_eq!((1.0, 0.0), wall.closest_point_to(&Pillar::at(1.0, 3.0)));
+    }
+
+    #[test]
+    fn clamps_closest_point_to_pillars() {
+        let pillars = [Pillar::at(0.0, 0.0), Pillar::at(4.0, 0.0)];
+        let wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+
+        assert_eq!((4.0, 0.0), wall.closest_point_to(&Pillar::at(6.0, -1.0)));
+    }
+
+    #[test]
+    fn measures_distance_to_wall_segment() {
+        let pillars = [Pillar::at(0.0, 0.0), Pillar::at(4.0, 0.0)];
+        let wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+
+        assert_eq!(3.0, wall.distance_from(&Pillar::at(2.0, 3.0)));
+        assert_eq!(5.0, wall.distance_from(&Pillar::at(7.0, 4.0)));
+    }
+
+    #[test]
+    fn sees_wall_crossing_view_with_pillars_out_of_sight() {
+        let pillars = [Pillar::at(3.0, -5.0), Pillar::at(3.0, 5.0)];
+        let wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+
+        assert!(wall.in_camera_view(&Camera::new()));
+    }
+
+    #[test]
+    fn does_not_see_wall_behind_camera() {
+        let pillars = [Pillar::at(-3.0, -5.0), Pillar::at(-3.0, 5.0)];
+        let wall = Wall::from_pillars(&pillars[0], &pillars[1]);
+
+        assert!(!wall.in_camera_view(&Camera::new()));
+    }
 }
@@ -12,6 +12,25 @@ pub fn normalize_range(original_angle: f64, range: Range<f64>) -> f64 {
     return normalized_angle + range.start;
 }
 
+/// Whether the line segment between the first pair of (x, y) points crosses the one between the second pair
+pub fn segments_cross(start1: (f64, f64), end1: (f64, f64), start2: (f64, f64), end2: (f64, f64)) -> bool {
+    let (x1, y1) = (end1.0 - start1.0, end1.1 - start1.1);
+    let (x2, y2) = (end2.0 - start2.0, end2.1 - start2.1);
+
+    let denominator = x1 * y2 - y1 * x2;
+    if denominator == 0.0 {
+        // Parallel segments never cross
+        return false;
+    }
+
+    let (start_x, start_y) = (start2.0 - start1.0, start2.1 - start1.1);
+    // How far along each segment the two lines meet
+    let along1 = (start_x * y2 - start_y * x2) / denominator;
+    let along2 = (start_x * y1 - start_y * x1) / denominator;
+
+    return (0.0..=1.0).contains(&along1) && (0.0..=1.0).contains(&along2);
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -23,4 +42,11 @@ mod tests {
     fn wraps_around_beginning() {
         assert_eq!(20.0, super::normalize_range(0.0, 1.0..21.0))
     }
+
+    #[test]
+    fn finds_crossing_segments() {
+        assert!(super::segments_cross((0.0, 0.0), (2.0, 2.0), (0.0, 2.0), (2.0, 0.0)));
+        assert!(!super::segments_cross((0.0, 0.0), (1.0, 1.0), (0.0, 4.0), (4.0, 0.0)));
+        assert!(!super::segments_cross((0.0, 0.0), (2.0, 0.0), (0.0, 1.0), (2.0, 1.0)));
+    }
 }